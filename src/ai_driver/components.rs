@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+
+pub const RACING_LINE_ITERATIONS: usize = 500;
+pub const RACING_LINE_EDGE_MARGIN: f32 = 1.5; // distance kept from the track edge (ft)
+
+/// path followed by an ai driver
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DrivingLine {
+    #[default]
+    Centerline,
+    RacingLine,
+}
+
+/// Steers a vehicle with pure pursuit on a lookahead point and sets the speed from the
+/// curvature ahead. Writes the VehicleInput of its vehicle.
+#[derive(Component)]
+pub struct AiDriver {
+    pub line: DrivingLine,
+    /// lookahead distance is speed * lookahead_time but at least min_lookahead
+    pub lookahead_time: f32,
+    pub min_lookahead: f32,
+    pub max_speed: f32,
+    pub max_lateral_acceleration: f32,
+    pub max_braking: f32,
+    /// throttle or brake per unit of speed error
+    pub speed_gain: f32,
+
+    // state
+    pub progress_index: usize,
+}
+
+impl Default for AiDriver {
+    fn default() -> Self {
+        AiDriver {
+            line: DrivingLine::Centerline,
+            lookahead_time: 0.4,
+            min_lookahead: 4.,
+            max_speed: 60.,
+            max_lateral_acceleration: 25.,
+            max_braking: 25.,
+            speed_gain: 0.5,
+
+            progress_index: 0,
+        }
+    }
+}
+
+/// lateral offsets (positive left) and curvature of the racing line for each centerline sample
+#[derive(Resource, Default)]
+pub struct RacingLineResource {
+    pub offsets: Vec<f32>,
+    pub curvatures: Vec<f32>,
+}
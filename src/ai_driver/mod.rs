@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+
+pub mod components;
+pub mod systems;
+
+use components::*;
+use systems::*;
+
+use crate::vehicle::systems::player_vehicle_input;
+
+pub struct AiDriverPlugin;
+
+impl Plugin for AiDriverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RacingLineResource>()
+            .add_system(update_racing_line)
+            .add_system(sync_autopilot)
+            // overrides keyboard input on vehicles with an ai driver
+            .add_system(drive_ai_vehicles.after(player_vehicle_input));
+    }
+}
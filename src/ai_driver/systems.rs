@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+
+use crate::{
+    ai_driver::components::*, components::*, track::components::*, vehicle::components::*,
+};
+
+/// refits the racing line when the centerline changes
+///
+/// The line is relaxed toward the midpoint of its neighbours (a taut string through the track)
+/// while staying inside the track edges, which cuts the apex of each corner.
+pub fn update_racing_line(
+    centerline_resource: Res<CenterlineResource>,
+    mut racing_line_resource: ResMut<RacingLineResource>,
+) {
+    if !centerline_resource.is_changed() {
+        return;
+    }
+
+    let points = &centerline_resource.points;
+    let count = points.len();
+
+    racing_line_resource.offsets = vec![0.; count];
+    racing_line_resource.curvatures = points.iter().map(|point| point.curvature).collect();
+
+    if count < 3 {
+        return;
+    }
+
+    // closed loops repeat the start as the last sample
+    let unique = if centerline_resource.closed {
+        count - 1
+    } else {
+        count
+    };
+
+    let mut offsets = vec![0.; count];
+    let line_point = |offsets: &[f32], index: usize| {
+        points[index].position + points[index].left() * offsets[index]
+    };
+
+    for _ in 0..RACING_LINE_ITERATIONS {
+        for index in 0..unique {
+            let (prev, next) = if centerline_resource.closed {
+                ((index + unique - 1) % unique, (index + 1) % unique)
+            } else if index == 0 || index == count - 1 {
+                continue;
+            } else {
+                (index - 1, index + 1)
+            };
+
+            let midpoint = (line_point(&offsets, prev) + line_point(&offsets, next)) / 2.;
+            let limit = (points[index].width / 2. - RACING_LINE_EDGE_MARGIN).max(0.);
+
+            offsets[index] = (midpoint - points[index].position)
+                .truncate()
+                .dot(points[index].left().truncate())
+                .clamp(-limit, limit);
+        }
+    }
+
+    if centerline_resource.closed {
+        offsets[count - 1] = offsets[0];
+    }
+
+    // signed curvature (positive left) through each point and its neighbours
+    let mut curvatures = vec![0.; count];
+    for (index, curvature) in curvatures.iter_mut().enumerate() {
+        let (prev, next) = if centerline_resource.closed {
+            ((index + unique - 1) % unique, (index + 1) % unique)
+        } else {
+            (index.saturating_sub(1), (index + 1).min(count - 1))
+        };
+        if prev == index || next == index {
+            continue;
+        }
+
+        let a = line_point(&offsets, prev).truncate();
+        let b = line_point(&offsets, index).truncate();
+        let c = line_point(&offsets, next).truncate();
+
+        let cross = (b - a).perp_dot(c - b);
+        let denominator = (b - a).length() * (c - b).length() * (c - a).length();
+        if denominator > 0. {
+            *curvature = 2. * cross / denominator;
+        }
+    }
+
+    racing_line_resource.offsets = offsets;
+    racing_line_resource.curvatures = curvatures;
+}
+
+/// position on the driving line at arc length s
+pub fn line_position(
+    centerline_resource: &CenterlineResource,
+    racing_line_resource: &RacingLineResource,
+    line: DrivingLine,
+    s: f32,
+) -> Vec3 {
+    let point = centerline_resource.sample(s);
+
+    let offset = match line {
+        DrivingLine::Centerline => 0.,
+        DrivingLine::RacingLine => {
+            let (index, fraction) = centerline_resource.index_at(s);
+            let offsets = &racing_line_resource.offsets;
+            match (offsets.get(index), offsets.get(index + 1)) {
+                (Some(a), Some(b)) => a + (b - a) * fraction,
+                (Some(a), None) => *a,
+                _ => 0.,
+            }
+        }
+    };
+
+    point.position + point.left() * offset
+}
+
+/// curvature of the driving line at centerline sample index
+pub fn line_curvature(
+    centerline_resource: &CenterlineResource,
+    racing_line_resource: &RacingLineResource,
+    line: DrivingLine,
+    index: usize,
+) -> f32 {
+    match line {
+        DrivingLine::Centerline => centerline_resource.points[index].curvature,
+        DrivingLine::RacingLine => racing_line_resource
+            .curvatures
+            .get(index)
+            .copied()
+            .unwrap_or(centerline_resource.points[index].curvature),
+    }
+}
+
+/// adds or removes the ai driver on the player vehicle to match the autopilot option
+pub fn sync_autopilot(
+    mut commands: Commands,
+    global_resource: Res<GlobalResource>,
+    mut vehicle_query: Query<(Entity, Option<&mut AiDriver>), With<PlayerControlled>>,
+) {
+    let line = if global_resource.racing_line {
+        DrivingLine::RacingLine
+    } else {
+        DrivingLine::Centerline
+    };
+
+    for (entity, driver) in vehicle_query.iter_mut() {
+        match (global_resource.autopilot, driver) {
+            (true, None) => {
+                commands
+                    .entity(entity)
+                    .insert(AiDriver { line, ..default() });
+            }
+            (true, Some(mut driver)) => {
+                if driver.line != line {
+                    driver.line = line;
+                }
+            }
+            (false, Some(_)) => {
+                commands.entity(entity).remove::<AiDriver>();
+            }
+            (false, None) => {}
+        }
+    }
+}
+
+/// pure pursuit steering and curvature limited speed control
+pub fn drive_ai_vehicles(
+    centerline_resource: Res<CenterlineResource>,
+    racing_line_resource: Res<RacingLineResource>,
+    mut vehicle_query: Query<(&Vehicle, &Transform, &mut VehicleInput, &mut AiDriver)>,
+) {
+    let points = &centerline_resource.points;
+    if points.len() < 2 {
+        return;
+    }
+
+    for (vehicle, transform, mut input, mut driver) in vehicle_query.iter_mut() {
+        let position = transform.translation;
+        let speed = vehicle.forward_speed(transform);
+
+        // progress along the track, searched locally so crossovers don't confuse it
+        let mut index = centerline_resource
+            .closest_index_near(position, driver.progress_index, TRACKING_WINDOW)
+            .unwrap_or(0);
+        if points[index]
+            .position
+            .truncate()
+            .distance(position.truncate())
+            > LOST_DISTANCE
+        {
+            index = centerline_resource.closest_index(position).unwrap_or(0);
+        }
+        driver.progress_index = index;
+        let s = points[index].s;
+
+        // steer along the arc through the lookahead point
+        let lookahead = (speed.abs() * driver.lookahead_time).max(driver.min_lookahead);
+        let target = line_position(
+            &centerline_resource,
+            &racing_line_resource,
+            driver.line,
+            s + lookahead,
+        );
+        let local_target = transform.rotation.inverse() * (target - position);
+        let path_curvature =
+            2. * local_target.y / local_target.truncate().length_squared().max(0.001);
+
+        let wheelbase = vehicle.wheel_mounts[0].x - vehicle.wheel_mounts[2].x;
+        let steer_angle = (wheelbase * path_curvature).atan();
+        input.steer = (steer_angle / vehicle.max_steer_angle).clamp(-1., 1.);
+
+        // slowest speed required by the corners within braking distance
+        let horizon = speed * speed / (2. * driver.max_braking) + lookahead;
+        let mut target_speed = driver.max_speed;
+        let mut distance = 0.;
+        let mut curr = index;
+        let mut steps = 0;
+
+        // each sample is visited at most once, a closed track of zero length never adds distance
+        while distance <= horizon && distance <= centerline_resource.length && steps < points.len()
+        {
+            steps += 1;
+            let curvature = line_curvature(
+                &centerline_resource,
+                &racing_line_resource,
+                driver.line,
+                curr,
+            )
+            .abs();
+            if curvature > 0. {
+                let corner_speed = (driver.max_lateral_acceleration / curvature).sqrt();
+                let allowed_speed =
+                    (corner_speed * corner_speed + 2. * driver.max_braking * distance).sqrt();
+                target_speed = target_speed.min(allowed_speed);
+            }
+
+            let next = curr + 1;
+            if next >= points.len() {
+                if centerline_resource.closed {
+                    curr = 0;
+                    continue;
+                }
+
+                // stop at the end of an open track
+                target_speed = target_speed.min((2. * driver.max_braking * distance).sqrt());
+                break;
+            }
+
+            distance += points[next].s - points[curr].s;
+            curr = next;
+        }
+
+        let speed_error = target_speed - speed;
+        input.throttle = (speed_error * driver.speed_gain).clamp(0., 1.);
+        input.brake = (-speed_error * driver.speed_gain).clamp(0., 1.);
+    }
+}
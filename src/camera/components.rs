@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use std::{fs, io, path::Path};
 
 // Camera model adaopted from: https://bevy-cheatbook.github.io/cookbook/pan-orbit-camera.html
 
@@ -8,6 +9,16 @@ pub struct PanOrbitCamera {
     pub focus: Vec3,
     pub radius: f32,
     pub upside_down: bool,
+    /// world axis kept pointing up on screen, the world is built Z-up
+    pub up: Vec3,
+    /// orbit around the up axis with yaw and pitch instead of tumbling freely
+    pub turntable: bool,
+    /// angle around the up axis (radians)
+    pub yaw: f32,
+    /// elevation above the plane normal to the up axis (radians)
+    pub pitch: f32,
+    pub min_pitch: f32,
+    pub max_pitch: f32,
 }
 
 impl Default for PanOrbitCamera {
@@ -16,6 +27,610 @@ impl Default for PanOrbitCamera {
             focus: Vec3::ZERO,
             radius: 5.0,
             upside_down: false,
+            up: Vec3::Z,
+            turntable: true,
+            yaw: 0.0,
+            pitch: 0.0,
+            min_pitch: (-10.0_f32).to_radians(),
+            max_pitch: 89.0_f32.to_radians(),
         }
     }
-}
\ No newline at end of file
+}
+
+impl PanOrbitCamera {
+    /// two axes spanning the plane normal to the up axis, yaw is measured from the first
+    fn horizontal_axes(&self) -> (Vec3, Vec3) {
+        let up = self.up.normalize();
+        let reference = if up.x.abs() < 0.9 { Vec3::X } else { Vec3::Y };
+        let forward = (reference - up * reference.dot(up)).normalize();
+        (forward, up.cross(forward))
+    }
+
+    /// direction from the focus point to the camera for the given angles
+    pub fn offset_direction(&self, yaw: f32, pitch: f32) -> Vec3 {
+        let (a, b) = self.horizontal_axes();
+        pitch.cos() * (yaw.cos() * a + yaw.sin() * b) + pitch.sin() * self.up.normalize()
+    }
+
+    /// camera transform from focus, radius, yaw and pitch
+    pub fn turntable_transform(&self) -> Transform {
+        let translation = self.focus + self.offset_direction(self.yaw, self.pitch) * self.radius;
+        Transform::from_translation(translation).looking_at(self.focus, self.up)
+    }
+
+    /// elevation of the camera above the plane normal to the up axis
+    pub fn elevation(&self, transform: &Transform) -> f32 {
+        let direction = (transform.rotation * Vec3::Z).normalize();
+        direction.dot(self.up.normalize()).clamp(-1.0, 1.0).asin()
+    }
+
+    /// derive yaw and pitch from a camera transform, e.g. after orbiting freely
+    pub fn sync_angles(&mut self, transform: &Transform) {
+        let (a, b) = self.horizontal_axes();
+        let direction = (transform.rotation * Vec3::Z).normalize();
+        self.pitch = self.elevation(transform);
+        self.yaw = direction.dot(b).atan2(direction.dot(a));
+    }
+}
+
+/// Orbit camera view that can be animated to, stored in bookmarks
+#[derive(Clone, PartialEq, Debug)]
+pub struct CameraView {
+    pub focus: Vec3,
+    pub radius: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl CameraView {
+    pub fn from_camera(pan_orbit: &PanOrbitCamera) -> Self {
+        CameraView {
+            focus: pan_orbit.focus,
+            radius: pan_orbit.radius,
+            yaw: pan_orbit.yaw,
+            pitch: pan_orbit.pitch,
+        }
+    }
+}
+
+/// Named camera view saved with the track
+#[derive(Clone, PartialEq, Debug)]
+pub struct CameraBookmark {
+    pub name: String,
+    pub view: CameraView,
+}
+
+/// Camera bookmarks of every track layout, stored by track_layout_hash in the bookmarks file
+#[derive(Resource, Default)]
+pub struct CameraBookmarkResource {
+    pub layouts: Vec<(u64, Vec<CameraBookmark>)>,
+    /// layout the bookmarks of the track resource belong to
+    pub layout_hash: Option<u64>,
+}
+
+impl CameraBookmarkResource {
+    pub fn bookmarks(&self, layout_hash: u64) -> Option<&Vec<CameraBookmark>> {
+        self.layouts
+            .iter()
+            .find(|(hash, _)| *hash == layout_hash)
+            .map(|(_, bookmarks)| bookmarks)
+    }
+
+    /// replaces the bookmarks of a layout, a layout without bookmarks is dropped
+    pub fn set_bookmarks(&mut self, layout_hash: u64, bookmarks: &[CameraBookmark]) {
+        self.layouts.retain(|(hash, _)| *hash != layout_hash);
+        if !bookmarks.is_empty() {
+            self.layouts.push((layout_hash, bookmarks.to_vec()));
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# camera bookmarks, layout hash = focus x y z, radius, yaw, pitch, name\n");
+        for (layout_hash, bookmarks) in &self.layouts {
+            for bookmark in bookmarks {
+                let view = &bookmark.view;
+                text += &format!(
+                    "{:016x} = {} {} {} {} {} {} {}\n",
+                    layout_hash, view.focus.x, view.focus.y, view.focus.z, view.radius, view.yaw, view.pitch, bookmark.name
+                );
+            }
+        }
+        text
+    }
+
+    /// reads a bookmarks file, lines that can't be read are skipped
+    pub fn from_text(text: &str) -> CameraBookmarkResource {
+        let mut bookmark_resource = CameraBookmarkResource::default();
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((layout_hash, value)) = line.split_once('=') else {
+                continue;
+            };
+            let Ok(layout_hash) = u64::from_str_radix(layout_hash.trim(), 16) else {
+                continue;
+            };
+
+            // six numbers, the rest of the line is the name
+            let mut parts = value.trim().splitn(7, ' ');
+            let numbers: Vec<f32> = parts.by_ref().take(6).filter_map(|part| part.parse().ok()).collect();
+            let (Some(name), &[x, y, z, radius, yaw, pitch]) = (parts.next(), &numbers[..]) else {
+                continue;
+            };
+
+            let bookmark = CameraBookmark {
+                name: name.to_string(),
+                view: CameraView { focus: Vec3::new(x, y, z), radius, yaw, pitch },
+            };
+            match bookmark_resource.layouts.iter_mut().find(|(hash, _)| *hash == layout_hash) {
+                Some((_, bookmarks)) => bookmarks.push(bookmark),
+                None => bookmark_resource.layouts.push((layout_hash, vec![bookmark])),
+            }
+        }
+
+        bookmark_resource
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_text())
+    }
+
+    pub fn load(path: &str) -> io::Result<CameraBookmarkResource> {
+        Ok(CameraBookmarkResource::from_text(&fs::read_to_string(path)?))
+    }
+}
+
+/// Framing commands for the orbit camera
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FramingCommand {
+    /// fit the whole terrain in view
+    FrameTrack,
+    /// fit the highlighted track element in view
+    FocusSelected,
+    /// go to the bookmark with this index
+    Bookmark(usize),
+}
+
+/// Smoothly moves the orbit camera to a target view, manual camera input cancels it
+#[derive(Component)]
+pub struct CameraTransition {
+    pub command: Option<FramingCommand>,
+    pub target: Option<CameraView>,
+    /// how quickly the camera approaches the target (1/s)
+    pub stiffness: f32,
+}
+
+impl Default for CameraTransition {
+    fn default() -> Self {
+        CameraTransition {
+            command: None,
+            target: None,
+            stiffness: 6.0,
+        }
+    }
+}
+
+/// Orthographic top-down view in plan view camera mode
+#[derive(Component)]
+pub struct PlanViewCamera {
+    /// point of the XY plane in the middle of the window
+    pub center: Vec2,
+    /// world height visible in the window
+    pub height: f32,
+    /// frame track and focus selected are handled, bookmarks need the orbit camera
+    pub command: Option<FramingCommand>,
+}
+
+impl Default for PlanViewCamera {
+    fn default() -> Self {
+        PlanViewCamera {
+            center: Vec2::new(0.0, 5.0),
+            height: 100.0,
+            command: None,
+        }
+    }
+}
+
+/// Follows the player vehicle in play mode
+#[derive(Component)]
+pub struct ChaseCamera {
+    pub distance: f32,
+    pub height: f32,
+    pub look_height: f32,
+    /// how quickly the camera catches up with the vehicle (1/s)
+    pub stiffness: f32,
+}
+
+impl Default for ChaseCamera {
+    fn default() -> Self {
+        ChaseCamera {
+            distance: 8.0,
+            height: 3.0,
+            look_height: 0.5,
+            stiffness: 5.0,
+        }
+    }
+}
+
+/// Travels along the track centerline in flythrough camera mode
+#[derive(Component)]
+pub struct FlythroughCamera {
+    /// arc length along the centerline
+    pub s: f32,
+    pub speed: f32,
+    pub height: f32,
+    /// roll the camera with the track banking
+    pub bank_roll: bool,
+    pub playing: bool,
+}
+
+impl Default for FlythroughCamera {
+    fn default() -> Self {
+        FlythroughCamera {
+            s: 0.0,
+            speed: 15.0,
+            height: 3.0,
+            bank_roll: true,
+            playing: true,
+        }
+    }
+}
+
+// camera input settings file, one "action = binding, binding" or "option = value" per line
+pub const CAMERA_SETTINGS_PATH: &str = "settings/camera_input.cfg";
+pub const CAMERA_BOOKMARKS_PATH: &str = "settings/camera_bookmarks.cfg";
+pub const GAMEPAD_DEAD_ZONE: f32 = 0.15;
+pub const FRAMING_MARGIN: f32 = 1.1; // room left around framed bounds
+pub const PLAN_VIEW_ALTITUDE: f32 = 500.0; // plan view camera height above the highest track point
+
+/// keys that can be bound to camera actions
+pub const BINDABLE_KEYS: [KeyCode; 58] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+    KeyCode::PageUp, KeyCode::PageDown, KeyCode::Home, KeyCode::End,
+    KeyCode::Space, KeyCode::Tab, KeyCode::Minus, KeyCode::Equals,
+    KeyCode::NumpadAdd, KeyCode::NumpadSubtract,
+    KeyCode::LShift, KeyCode::RShift, KeyCode::LControl, KeyCode::RControl,
+    KeyCode::LAlt, KeyCode::RAlt, KeyCode::LWin, KeyCode::RWin,
+];
+
+/// modifier keys combine with a mouse button, e.g. alt + left drag to orbit on a trackpad
+pub const MODIFIER_KEYS: [KeyCode; 8] = [
+    KeyCode::LShift, KeyCode::RShift, KeyCode::LControl, KeyCode::RControl,
+    KeyCode::LAlt, KeyCode::RAlt, KeyCode::LWin, KeyCode::RWin,
+];
+
+pub const MOUSE_BUTTONS: [MouseButton; 3] = [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+/// orbit camera actions that can be bound to keys and mouse buttons
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CameraAction {
+    Orbit,
+    Pan,
+    FlyForward,
+    FlyBack,
+    FlyLeft,
+    FlyRight,
+    FlyUp,
+    FlyDown,
+    OrbitLeft,
+    OrbitRight,
+    OrbitUp,
+    OrbitDown,
+    ZoomIn,
+    ZoomOut,
+    FrameTrack,
+    FocusSelected,
+}
+
+impl CameraAction {
+    pub const ALL: [CameraAction; 16] = [
+        CameraAction::Orbit,
+        CameraAction::Pan,
+        CameraAction::FlyForward,
+        CameraAction::FlyBack,
+        CameraAction::FlyLeft,
+        CameraAction::FlyRight,
+        CameraAction::FlyUp,
+        CameraAction::FlyDown,
+        CameraAction::OrbitLeft,
+        CameraAction::OrbitRight,
+        CameraAction::OrbitUp,
+        CameraAction::OrbitDown,
+        CameraAction::ZoomIn,
+        CameraAction::ZoomOut,
+        CameraAction::FrameTrack,
+        CameraAction::FocusSelected,
+    ];
+
+    /// name used in the settings file
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraAction::Orbit => "orbit",
+            CameraAction::Pan => "pan",
+            CameraAction::FlyForward => "fly_forward",
+            CameraAction::FlyBack => "fly_back",
+            CameraAction::FlyLeft => "fly_left",
+            CameraAction::FlyRight => "fly_right",
+            CameraAction::FlyUp => "fly_up",
+            CameraAction::FlyDown => "fly_down",
+            CameraAction::OrbitLeft => "orbit_left",
+            CameraAction::OrbitRight => "orbit_right",
+            CameraAction::OrbitUp => "orbit_up",
+            CameraAction::OrbitDown => "orbit_down",
+            CameraAction::ZoomIn => "zoom_in",
+            CameraAction::ZoomOut => "zoom_out",
+            CameraAction::FrameTrack => "frame_track",
+            CameraAction::FocusSelected => "focus_selected",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CameraAction::Orbit => "Orbit (drag)",
+            CameraAction::Pan => "Pan (drag)",
+            CameraAction::FlyForward => "Fly Forward",
+            CameraAction::FlyBack => "Fly Back",
+            CameraAction::FlyLeft => "Fly Left",
+            CameraAction::FlyRight => "Fly Right",
+            CameraAction::FlyUp => "Fly Up",
+            CameraAction::FlyDown => "Fly Down",
+            CameraAction::OrbitLeft => "Orbit Left",
+            CameraAction::OrbitRight => "Orbit Right",
+            CameraAction::OrbitUp => "Orbit Up",
+            CameraAction::OrbitDown => "Orbit Down",
+            CameraAction::ZoomIn => "Zoom In",
+            CameraAction::ZoomOut => "Zoom Out",
+            CameraAction::FrameTrack => "Frame Track",
+            CameraAction::FocusSelected => "Focus Selected",
+        }
+    }
+}
+
+/// a key, a mouse button or a modifier key held with a mouse button
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum InputBinding {
+    #[default]
+    Unbound,
+    Key(KeyCode),
+    Mouse(MouseButton),
+    ModifiedMouse(KeyCode, MouseButton),
+}
+
+impl InputBinding {
+    pub fn pressed(&self, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        match *self {
+            InputBinding::Unbound => false,
+            InputBinding::Key(key) => keys.pressed(key),
+            InputBinding::Mouse(button) => mouse.pressed(button),
+            InputBinding::ModifiedMouse(key, button) => keys.pressed(key) && mouse.pressed(button),
+        }
+    }
+
+    pub fn just_pressed(&self, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        match *self {
+            InputBinding::Unbound => false,
+            InputBinding::Key(key) => keys.just_pressed(key),
+            InputBinding::Mouse(button) => mouse.just_pressed(button),
+            InputBinding::ModifiedMouse(key, button) => keys.pressed(key) && mouse.just_pressed(button),
+        }
+    }
+
+    /// true on the frame the binding became or stopped being active
+    pub fn changed(&self, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        match *self {
+            InputBinding::Unbound => false,
+            InputBinding::Key(key) => keys.just_pressed(key) || keys.just_released(key),
+            InputBinding::Mouse(button) | InputBinding::ModifiedMouse(_, button) => {
+                mouse.just_pressed(button) || mouse.just_released(button)
+            }
+        }
+    }
+
+    /// text used in the settings file and shown in the ui, e.g. "LAlt+Mouse(Left)"
+    pub fn to_text(&self) -> String {
+        match self {
+            InputBinding::Unbound => String::from("-"),
+            InputBinding::Key(key) => format!("{key:?}"),
+            InputBinding::Mouse(button) => format!("Mouse({button:?})"),
+            InputBinding::ModifiedMouse(key, button) => format!("{key:?}+Mouse({button:?})"),
+        }
+    }
+
+    pub fn from_text(text: &str) -> Option<InputBinding> {
+        let text = text.trim();
+        if text == "-" {
+            return Some(InputBinding::Unbound);
+        }
+
+        let parse_key = |text: &str| BINDABLE_KEYS.into_iter().find(|key| format!("{key:?}") == text);
+        let parse_mouse = |text: &str| {
+            let name = text.strip_prefix("Mouse(")?.strip_suffix(')')?;
+            MOUSE_BUTTONS.into_iter().find(|button| format!("{button:?}") == name)
+        };
+
+        match text.split_once('+') {
+            Some((key, button)) => Some(InputBinding::ModifiedMouse(parse_key(key)?, parse_mouse(button)?)),
+            None => parse_key(text)
+                .map(InputBinding::Key)
+                .or_else(|| parse_mouse(text).map(InputBinding::Mouse)),
+        }
+    }
+}
+
+/// two alternative bindings for one action
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ActionBinding {
+    pub action: CameraAction,
+    pub bindings: [InputBinding; 2],
+}
+
+/// Maps keyboard, mouse and gamepad input to orbit camera actions. Loaded from and saved to
+/// the camera settings file.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct CameraInputMap {
+    pub action_bindings: Vec<ActionBinding>,
+    /// keyboard and gamepad fly speed in camera radii per second
+    pub fly_speed: f32,
+    /// keyboard and gamepad orbit speed (radians per second)
+    pub orbit_speed: f32,
+    pub invert_y: bool,
+    pub gamepad_enabled: bool,
+}
+
+impl Default for CameraInputMap {
+    fn default() -> Self {
+        use InputBinding::*;
+
+        let binding = |action, primary, secondary| ActionBinding {
+            action,
+            bindings: [primary, secondary],
+        };
+
+        CameraInputMap {
+            action_bindings: vec![
+                binding(CameraAction::Orbit, Mouse(MouseButton::Right), ModifiedMouse(KeyCode::LAlt, MouseButton::Left)),
+                binding(CameraAction::Pan, Mouse(MouseButton::Middle), ModifiedMouse(KeyCode::LShift, MouseButton::Left)),
+                binding(CameraAction::FlyForward, Key(KeyCode::W), Unbound),
+                binding(CameraAction::FlyBack, Key(KeyCode::S), Unbound),
+                binding(CameraAction::FlyLeft, Key(KeyCode::A), Unbound),
+                binding(CameraAction::FlyRight, Key(KeyCode::D), Unbound),
+                binding(CameraAction::FlyUp, Key(KeyCode::E), Unbound),
+                binding(CameraAction::FlyDown, Key(KeyCode::Q), Unbound),
+                binding(CameraAction::OrbitLeft, Key(KeyCode::Left), Unbound),
+                binding(CameraAction::OrbitRight, Key(KeyCode::Right), Unbound),
+                binding(CameraAction::OrbitUp, Key(KeyCode::Up), Unbound),
+                binding(CameraAction::OrbitDown, Key(KeyCode::Down), Unbound),
+                binding(CameraAction::ZoomIn, Key(KeyCode::Equals), Key(KeyCode::NumpadAdd)),
+                binding(CameraAction::ZoomOut, Key(KeyCode::Minus), Key(KeyCode::NumpadSubtract)),
+                binding(CameraAction::FrameTrack, Key(KeyCode::Home), Unbound),
+                binding(CameraAction::FocusSelected, Key(KeyCode::F), Unbound),
+            ],
+            fly_speed: 1.0,
+            orbit_speed: 1.5,
+            invert_y: false,
+            gamepad_enabled: true,
+        }
+    }
+}
+
+impl CameraInputMap {
+    pub fn bindings(&self, action: CameraAction) -> [InputBinding; 2] {
+        self.action_bindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .map_or([InputBinding::Unbound; 2], |binding| binding.bindings)
+    }
+
+    pub fn set_binding(&mut self, action: CameraAction, slot: usize, binding: InputBinding) {
+        match self.action_bindings.iter_mut().find(|binding| binding.action == action) {
+            Some(action_binding) => action_binding.bindings[slot] = binding,
+            None => {
+                let mut bindings = [InputBinding::Unbound; 2];
+                bindings[slot] = binding;
+                self.action_bindings.push(ActionBinding { action, bindings });
+            }
+        }
+    }
+
+    pub fn pressed(&self, action: CameraAction, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        self.bindings(action).iter().any(|binding| binding.pressed(keys, mouse))
+    }
+
+    pub fn just_pressed(&self, action: CameraAction, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        self.bindings(action).iter().any(|binding| binding.just_pressed(keys, mouse))
+    }
+
+    pub fn changed(&self, action: CameraAction, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        self.bindings(action).iter().any(|binding| binding.changed(keys, mouse))
+    }
+
+    /// -1, 0 or 1 from a pair of opposing actions
+    pub fn axis(&self, negative: CameraAction, positive: CameraAction, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> f32 {
+        self.pressed(positive, keys, mouse) as i32 as f32 - self.pressed(negative, keys, mouse) as i32 as f32
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# camera input settings\n");
+        for action_binding in &self.action_bindings {
+            text += &format!(
+                "{} = {}, {}\n",
+                action_binding.action.name(),
+                action_binding.bindings[0].to_text(),
+                action_binding.bindings[1].to_text()
+            );
+        }
+        text += &format!("fly_speed = {}\n", self.fly_speed);
+        text += &format!("orbit_speed = {}\n", self.orbit_speed);
+        text += &format!("invert_y = {}\n", self.invert_y);
+        text += &format!("gamepad_enabled = {}\n", self.gamepad_enabled);
+        text
+    }
+
+    /// reads a settings file, unknown lines and values are skipped and keep their defaults
+    pub fn from_text(text: &str) -> CameraInputMap {
+        let mut input_map = CameraInputMap::default();
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (name, value) = (name.trim(), value.trim());
+
+            match name {
+                "fly_speed" => input_map.fly_speed = value.parse().unwrap_or(input_map.fly_speed),
+                "orbit_speed" => input_map.orbit_speed = value.parse().unwrap_or(input_map.orbit_speed),
+                "invert_y" => input_map.invert_y = value.parse().unwrap_or(input_map.invert_y),
+                "gamepad_enabled" => {
+                    input_map.gamepad_enabled = value.parse().unwrap_or(input_map.gamepad_enabled)
+                }
+                _ => {
+                    let Some(action) = CameraAction::ALL.into_iter().find(|action| action.name() == name) else {
+                        continue;
+                    };
+                    for (slot, binding) in value.split(',').take(2).enumerate() {
+                        if let Some(binding) = InputBinding::from_text(binding) {
+                            input_map.set_binding(action, slot, binding);
+                        }
+                    }
+                }
+            }
+        }
+
+        input_map
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_text())
+    }
+
+    pub fn load(path: &str) -> io::Result<CameraInputMap> {
+        Ok(CameraInputMap::from_text(&fs::read_to_string(path)?))
+    }
+}
+
+/// action and binding slot waiting for the next key or mouse button, plus the last settings
+/// file message
+#[derive(Resource, Default)]
+pub struct CameraRebindResource {
+    pub pending: Option<(CameraAction, usize)>,
+    pub status: String,
+}
@@ -3,13 +3,27 @@ use bevy::prelude::*;
 pub mod components;
 mod systems;
 
+use components::*;
 use systems::*;
 
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(spawn_camera)
-        .add_system(pan_orbit_camera);
+        app.init_resource::<CameraInputMap>()
+        .init_resource::<CameraRebindResource>()
+        .init_resource::<CameraBookmarkResource>()
+        .add_startup_system(spawn_camera)
+        .add_startup_system(load_camera_input)
+        .add_startup_system(load_camera_bookmarks)
+        .add_system(sync_camera_bookmarks)
+        .add_system(capture_rebinding)
+        .add_system(save_camera_input.after(capture_rebinding))
+        .add_system(restore_orbit_camera)
+        .add_system(camera_framing.after(restore_orbit_camera))
+        .add_system(pan_orbit_camera.after(camera_framing))
+        .add_system(plan_view_camera.after(restore_orbit_camera))
+        .add_system(chase_camera.after(restore_orbit_camera))
+        .add_system(flythrough_camera.after(restore_orbit_camera));
     }
 }
\ No newline at end of file
@@ -1,23 +1,42 @@
 use bevy::prelude::*;
 use bevy::input::mouse::{MouseWheel,MouseMotion};
-use bevy::render::camera::Projection;
+use bevy::render::camera::{Projection, ScalingMode};
 use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
 
 use crate::camera::components::*;
+use crate::components::*;
+use crate::tools::raycast::mesh_world_bounds;
+use crate::track::components::CenterlineResource;
+use crate::vehicle::components::{PlayerControlled, Vehicle};
 
 // Camera model adaopted from: https://bevy-cheatbook.github.io/cookbook/pan-orbit-camera.html
 
-/// Pan the camera with middle mouse click, zoom with scroll wheel, orbit with right mouse click.
+/// Pan, orbit and zoom the camera with the mouse, fly and orbit with the keyboard or a gamepad.
+/// The bindings come from the CameraInputMap.
+#[allow(clippy::too_many_arguments)]
 pub fn pan_orbit_camera(
     window_query: Query<&Window, With<PrimaryWindow>>,
     mut ev_motion: EventReader<MouseMotion>,
     mut ev_scroll: EventReader<MouseWheel>,
     input_mouse: Res<Input<MouseButton>>,
-    mut query: Query<(&mut PanOrbitCamera, &mut Transform, &Projection)>,
+    input_keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    time: Res<Time>,
+    input_map: Res<CameraInputMap>,
+    rebind_resource: Res<CameraRebindResource>,
+    global_resource: Res<GlobalResource>,
+    mut contexts: EguiContexts,
+    mut query: Query<(&mut PanOrbitCamera, &mut Transform, &Projection, Option<&mut CameraTransition>)>,
 ) {
-    // change input mapping for orbit and panning here
-    let orbit_button = MouseButton::Right;
-    let pan_button = MouseButton::Middle;
+    // other camera modes own the transform, and input is being captured while rebinding
+    if !orbit_camera_active(&global_resource) || rebind_resource.pending.is_some() {
+        ev_motion.clear();
+        ev_scroll.clear();
+        return;
+    }
 
     let mut pan = Vec2::ZERO;
     let mut rotation_move = Vec2::ZERO;
@@ -26,11 +45,11 @@ pub fn pan_orbit_camera(
 
     let window = window_query.get_single().unwrap();
 
-    if input_mouse.pressed(orbit_button) {
+    if input_map.pressed(CameraAction::Orbit, &input_keys, &input_mouse) {
         for ev in ev_motion.iter() {
             rotation_move += ev.delta;
         }
-    } else if input_mouse.pressed(pan_button) {
+    } else if input_map.pressed(CameraAction::Pan, &input_keys, &input_mouse) {
         // Pan only if we're not rotating at the moment
         for ev in ev_motion.iter() {
             pan += ev.delta;
@@ -39,31 +58,91 @@ pub fn pan_orbit_camera(
     for ev in ev_scroll.iter() {
         scroll += ev.y;
     }
-    if input_mouse.just_released(orbit_button) || input_mouse.just_pressed(orbit_button) {
+    if input_map.changed(CameraAction::Orbit, &input_keys, &input_mouse) {
         orbit_button_changed = true;
     }
+    if input_map.invert_y {
+        rotation_move.y = -rotation_move.y;
+    }
+
+    // rates from keys and gamepad sticks, fly is (right, forward, up), orbit is (yaw, pitch)
+    let mut fly = Vec3::ZERO;
+    let mut orbit = Vec2::ZERO;
+    let mut zoom = 0.0;
+
+    // leave the keyboard to text fields
+    if !contexts.ctx_mut().wants_keyboard_input() {
+        let axis = |negative, positive| input_map.axis(negative, positive, &input_keys, &input_mouse);
+        fly += Vec3::new(
+            axis(CameraAction::FlyLeft, CameraAction::FlyRight),
+            axis(CameraAction::FlyBack, CameraAction::FlyForward),
+            axis(CameraAction::FlyDown, CameraAction::FlyUp),
+        );
+        orbit += Vec2::new(
+            axis(CameraAction::OrbitLeft, CameraAction::OrbitRight),
+            axis(CameraAction::OrbitDown, CameraAction::OrbitUp),
+        );
+        zoom += axis(CameraAction::ZoomOut, CameraAction::ZoomIn);
+    }
+
+    // left stick flies, right stick orbits, bumpers fly up and down, triggers zoom
+    if input_map.gamepad_enabled {
+        for gamepad in gamepads.iter() {
+            let stick = |axis_type| {
+                let value = gamepad_axes.get(GamepadAxis::new(gamepad, axis_type)).unwrap_or(0.0);
+                if value.abs() < GAMEPAD_DEAD_ZONE { 0.0 } else { value }
+            };
+            let button = |button_type| gamepad_buttons.pressed(GamepadButton::new(gamepad, button_type)) as i32 as f32;
+
+            fly += Vec3::new(
+                stick(GamepadAxisType::LeftStickX),
+                stick(GamepadAxisType::LeftStickY),
+                button(GamepadButtonType::RightTrigger) - button(GamepadButtonType::LeftTrigger),
+            );
+            let pitch = stick(GamepadAxisType::RightStickY);
+            orbit += Vec2::new(
+                stick(GamepadAxisType::RightStickX),
+                if input_map.invert_y { pitch } else { -pitch },
+            );
+            zoom += button(GamepadButtonType::RightTrigger2) - button(GamepadButtonType::LeftTrigger2);
+        }
+    }
 
-    for (mut pan_orbit, mut transform, projection) in query.iter_mut() {
+    let delta_seconds = time.delta_seconds();
+
+    for (mut pan_orbit, mut transform, projection, transition) in query.iter_mut() {
         if orbit_button_changed {
             // only check for upside down when orbiting started or ended this frame
             // if the camera is "upside" down, panning horizontally would be inverted, so invert the input to make it correct
             let up = transform.rotation * Vec3::Y;
-            pan_orbit.upside_down = up.y <= 0.0;
+            pan_orbit.upside_down = up.dot(pan_orbit.up) <= 0.0;
         }
 
         let mut any = false;
-        if rotation_move.length_squared() > 0.0 {
+        if rotation_move.length_squared() > 0.0 || orbit.length_squared() > 0.0 {
             any = true;
             let window_size = Vec2::new(window.width(), window.height());
             let delta_x = {
-                let delta = rotation_move.x / window_size.x * std::f32::consts::PI * 2.0;
-                if pan_orbit.upside_down { -delta } else { delta }
+                let delta = rotation_move.x / window_size.x * std::f32::consts::PI * 2.0
+                    + orbit.x * input_map.orbit_speed * delta_seconds;
+                if pan_orbit.upside_down && !pan_orbit.turntable { -delta } else { delta }
             };
-            let delta_y = rotation_move.y / window_size.y * std::f32::consts::PI;
-            let yaw = Quat::from_rotation_y(-delta_x);
-            let pitch = Quat::from_rotation_x(-delta_y);
-            transform.rotation = yaw * transform.rotation; // rotate around global y axis
-            transform.rotation = transform.rotation * pitch; // rotate around local x axis
+            let delta_y = rotation_move.y / window_size.y * std::f32::consts::PI
+                + orbit.y * input_map.orbit_speed * delta_seconds;
+            if pan_orbit.turntable {
+                pan_orbit.yaw -= delta_x;
+                pan_orbit.pitch = (pan_orbit.pitch + delta_y).clamp(pan_orbit.min_pitch, pan_orbit.max_pitch);
+            } else {
+                let yaw = Quat::from_axis_angle(pan_orbit.up.normalize(), -delta_x);
+                let pitch = Quat::from_rotation_x(-delta_y);
+                transform.rotation = yaw * transform.rotation; // rotate around the up axis
+                let pitched = Transform::from_rotation(transform.rotation * pitch); // rotate around local x axis
+                // skip pitch steps that leave the pitch limits
+                let elevation = pan_orbit.elevation(&pitched);
+                if elevation >= pan_orbit.min_pitch && elevation <= pan_orbit.max_pitch {
+                    transform.rotation = pitched.rotation;
+                }
+            }
         } else if pan.length_squared() > 0.0 {
             any = true;
             // make panning distance independent of resolution and FOV,
@@ -84,12 +163,45 @@ pub fn pan_orbit_camera(
             pan_orbit.radius = f32::max(pan_orbit.radius, 0.05);
         }
 
+        if fly.length_squared() > 0.0 {
+            any = true;
+            // fly in the plane normal to the up axis along the camera heading
+            let up = pan_orbit.up.normalize();
+            let mut forward = transform.forward() - up * transform.forward().dot(up);
+            if forward.length_squared() < 1e-6 {
+                // looking straight along the up axis
+                forward = transform.up() - up * transform.up().dot(up);
+            }
+            let forward = forward.normalize_or_zero();
+            let right = forward.cross(up);
+            let step = input_map.fly_speed * pan_orbit.radius * delta_seconds;
+            pan_orbit.focus += (right * fly.x + forward * fly.y + up * fly.z) * step;
+        }
+        if zoom != 0.0 {
+            any = true;
+            pan_orbit.radius = f32::max(pan_orbit.radius * (-zoom * 2.0 * delta_seconds).exp(), 0.05);
+        }
+
+        // taking over the camera ends a framing transition
         if any {
-            // emulating parent/child to make the yaw/y-axis rotation behave like a turntable
-            // parent = x and y rotation
-            // child = z-offset
-            let rot_matrix = Mat3::from_quat(transform.rotation);
-            transform.translation = pan_orbit.focus + rot_matrix.mul_vec3(Vec3::new(0.0, 0.0, pan_orbit.radius));
+            if let Some(mut transition) = transition {
+                transition.target = None;
+            }
+        }
+
+        // settings changed elsewhere (ui, framing) also move the camera
+        if any || pan_orbit.is_changed() {
+            if pan_orbit.turntable {
+                *transform = pan_orbit.turntable_transform();
+            } else {
+                // emulating parent/child to make the yaw rotation behave like a turntable
+                // parent = x and y rotation
+                // child = z-offset
+                let rot_matrix = Mat3::from_quat(transform.rotation);
+                transform.translation = pan_orbit.focus + rot_matrix.mul_vec3(Vec3::new(0.0, 0.0, pan_orbit.radius));
+                // keep the angles current so switching to turntable doesn't jump
+                pan_orbit.sync_angles(&transform);
+            }
         }
     }
 
@@ -98,20 +210,493 @@ pub fn pan_orbit_camera(
     ev_motion.clear();
 }
 
-/// Spawn a camera 
+/// Spawn a camera looking down on the start of the track, Z up
 pub fn spawn_camera(mut commands: Commands) {
-    let translation = Vec3::new(-2.0, 2.5, 5.0);
-    let radius = translation.length();
+    let pan_orbit = PanOrbitCamera {
+        focus: Vec3::new(0.0, 5.0, 0.0),
+        radius: 50.0,
+        yaw: (-90.0_f32).to_radians(),
+        pitch: 40.0_f32.to_radians(),
+        ..Default::default()
+    };
 
     commands.spawn((
         Camera3dBundle {
-            transform: Transform::from_translation(translation)
-                .looking_at(Vec3::ZERO, Vec3::Y),
-            ..Default::default()
-        },
-        PanOrbitCamera {
-            radius,
+            transform: pan_orbit.turntable_transform(),
             ..Default::default()
         },
+        pan_orbit,
+        CameraTransition::default(),
+        PlanViewCamera::default(),
+        ChaseCamera::default(),
+        FlythroughCamera::default(),
     ));
-}
\ No newline at end of file
+}
+
+/// Turn framing commands into a target view and ease the orbit camera towards it
+#[allow(clippy::too_many_arguments)]
+pub fn camera_framing(
+    time: Res<Time>,
+    input_keys: Res<Input<KeyCode>>,
+    input_mouse: Res<Input<MouseButton>>,
+    input_map: Res<CameraInputMap>,
+    global_resource: Res<GlobalResource>,
+    track_resource: Res<TrackResource>,
+    mesh_resource: Res<MeshResource>,
+    meshes: Res<Assets<Mesh>>,
+    mut contexts: EguiContexts,
+    mut query: Query<(&mut PanOrbitCamera, &mut CameraTransition, &mut Transform, &Projection)>,
+) {
+    if !orbit_camera_active(&global_resource) {
+        return;
+    }
+
+    let mut key_command = None;
+    if !contexts.ctx_mut().wants_keyboard_input() {
+        if input_map.just_pressed(CameraAction::FrameTrack, &input_keys, &input_mouse) {
+            key_command = Some(FramingCommand::FrameTrack);
+        } else if input_map.just_pressed(CameraAction::FocusSelected, &input_keys, &input_mouse) {
+            key_command = Some(FramingCommand::FocusSelected);
+        }
+    }
+
+    for (mut pan_orbit, mut transition, mut transform, projection) in query.iter_mut() {
+        if let Some(command) = transition.command.take().or(key_command) {
+            let current = CameraView::from_camera(&pan_orbit);
+
+            transition.target = match command {
+                FramingCommand::FrameTrack => {
+                    // the terrain covers the track plus a margin
+                    let min = Vec3::new(global_resource.x_min, global_resource.y_min, global_resource.min_track_ht);
+                    let max = Vec3::new(global_resource.x_max, global_resource.y_max, global_resource.max_track_ht);
+                    framed_view(&current, min, max, projection)
+                }
+                FramingCommand::FocusSelected => {
+                    usize::try_from(global_resource.highlighted_track_index)
+                        .ok()
+                        .and_then(|index| {
+                            let mesh = meshes.get(mesh_resource.track_mesh_list.get(index)?)?;
+                            mesh_world_bounds(mesh, mesh_resource.track_mesh_transform_list.get(index)?)
+                        })
+                        .and_then(|(min, max)| framed_view(&current, min, max, projection))
+                }
+                FramingCommand::Bookmark(index) => track_resource
+                    .camera_bookmarks
+                    .get(index)
+                    .map(|bookmark| bookmark.view.clone()),
+            };
+        }
+
+        let Some(target) = transition.target.clone() else {
+            continue;
+        };
+
+        let blend = 1.0 - (-transition.stiffness * time.delta_seconds()).exp();
+        let yaw_error = (target.yaw - pan_orbit.yaw + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+        let rotating = yaw_error.abs() > 1e-4 || (target.pitch - pan_orbit.pitch).abs() > 1e-4;
+
+        let done = target.focus.distance(pan_orbit.focus) < 0.01 * target.radius
+            && (target.radius / pan_orbit.radius).ln().abs() < 0.01
+            && !rotating;
+
+        if done {
+            pan_orbit.focus = target.focus;
+            pan_orbit.radius = target.radius;
+            pan_orbit.yaw = target.yaw;
+            pan_orbit.pitch = target.pitch;
+            transition.target = None;
+        } else {
+            pan_orbit.focus = pan_orbit.focus.lerp(target.focus, blend);
+            // zoom evenly on a log scale
+            pan_orbit.radius *= (target.radius / pan_orbit.radius).powf(blend);
+            pan_orbit.yaw += yaw_error * blend;
+            pan_orbit.pitch += (target.pitch - pan_orbit.pitch) * blend;
+        }
+
+        // the free orbit camera keeps its own rotation, only turn it when the view angles change
+        if !pan_orbit.turntable && (rotating || done) {
+            *transform = pan_orbit.turntable_transform();
+        }
+    }
+}
+
+/// keep the view angles and move back far enough to see the box from min to max
+fn framed_view(current: &CameraView, min: Vec3, max: Vec3, projection: &Projection) -> Option<CameraView> {
+    let bounds_radius = 0.5 * (max - min).length();
+    if !bounds_radius.is_finite() || bounds_radius < 0.01 {
+        return None;
+    }
+
+    // half of the narrower field of view
+    let half_angle = match projection {
+        Projection::Perspective(projection) => {
+            let horizontal = 2.0 * ((0.5 * projection.fov).tan() * projection.aspect_ratio).atan();
+            0.5 * projection.fov.min(horizontal)
+        }
+        Projection::Orthographic(_) => 0.4,
+    };
+
+    Some(CameraView {
+        focus: 0.5 * (min + max),
+        radius: FRAMING_MARGIN * bounds_radius / half_angle.sin(),
+        ..current.clone()
+    })
+}
+
+/// Look straight down with an orthographic projection in plan view mode. Drag with the orbit
+/// or pan binding to pan, scroll or the zoom keys to zoom and the fly keys to move.
+#[allow(clippy::too_many_arguments)]
+pub fn plan_view_camera(
+    mut was_active: Local<bool>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut ev_motion: EventReader<MouseMotion>,
+    mut ev_scroll: EventReader<MouseWheel>,
+    input_mouse: Res<Input<MouseButton>>,
+    input_keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    input_map: Res<CameraInputMap>,
+    rebind_resource: Res<CameraRebindResource>,
+    global_resource: Res<GlobalResource>,
+    centerline_resource: Res<CenterlineResource>,
+    mut contexts: EguiContexts,
+    mut query: Query<(&mut PlanViewCamera, &mut Transform, &mut Projection)>,
+) {
+    let active = global_resource.app_mode == AppMode::Editor && global_resource.camera_mode == CameraMode::PlanView;
+
+    if !active {
+        // hand the camera back with the projection it was spawned with
+        if *was_active {
+            for (_, _, mut projection) in query.iter_mut() {
+                *projection = Projection::Perspective(PerspectiveProjection::default());
+            }
+        }
+        *was_active = false;
+        ev_motion.clear();
+        ev_scroll.clear();
+        return;
+    }
+
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    let mut drag = Vec2::ZERO;
+    let mut scroll = 0.0;
+    let capturing = rebind_resource.pending.is_some();
+
+    if !capturing
+        && (input_map.pressed(CameraAction::Orbit, &input_keys, &input_mouse)
+            || input_map.pressed(CameraAction::Pan, &input_keys, &input_mouse))
+    {
+        for ev in ev_motion.iter() {
+            drag += ev.delta;
+        }
+    }
+    for ev in ev_scroll.iter() {
+        scroll += ev.y;
+    }
+    ev_motion.clear();
+
+    let mut fly = Vec2::ZERO;
+    let mut zoom = 0.0;
+    let mut key_command = None;
+
+    if !capturing && !contexts.ctx_mut().wants_keyboard_input() {
+        let axis = |negative, positive| input_map.axis(negative, positive, &input_keys, &input_mouse);
+        fly = Vec2::new(
+            axis(CameraAction::FlyLeft, CameraAction::FlyRight),
+            axis(CameraAction::FlyBack, CameraAction::FlyForward),
+        );
+        zoom = axis(CameraAction::ZoomOut, CameraAction::ZoomIn);
+
+        if input_map.just_pressed(CameraAction::FrameTrack, &input_keys, &input_mouse) {
+            key_command = Some(FramingCommand::FrameTrack);
+        } else if input_map.just_pressed(CameraAction::FocusSelected, &input_keys, &input_mouse) {
+            key_command = Some(FramingCommand::FocusSelected);
+        }
+    }
+
+    let delta_seconds = time.delta_seconds();
+    let window_size = Vec2::new(window.width(), window.height());
+
+    for (mut plan_view, mut transform, mut projection) in query.iter_mut() {
+        if let Some(command) = plan_view.command.take().or(key_command) {
+            let bounds = match command {
+                FramingCommand::FrameTrack => Some((
+                    Vec2::new(global_resource.x_min, global_resource.y_min),
+                    Vec2::new(global_resource.x_max, global_resource.y_max),
+                )),
+                FramingCommand::FocusSelected => element_plan_bounds(&centerline_resource, global_resource.highlighted_track_index),
+                FramingCommand::Bookmark(_) => None,
+            };
+
+            if let Some((min, max)) = bounds {
+                let size = max - min;
+                if size.min_element() > 0.0 {
+                    plan_view.center = 0.5 * (min + max);
+                    plan_view.height = FRAMING_MARGIN * size.y.max(size.x * window_size.y / window_size.x);
+                }
+            }
+        }
+
+        // world units per window pixel
+        let pixel_size = plan_view.height / window_size.y;
+
+        plan_view.center += Vec2::new(-drag.x, drag.y) * pixel_size;
+        let step = input_map.fly_speed * plan_view.height * delta_seconds;
+        plan_view.center += fly * step;
+
+        if scroll.abs() > 0.0 {
+            plan_view.height -= scroll * plan_view.height * 0.2;
+        }
+        plan_view.height *= (-zoom * 2.0 * delta_seconds).exp();
+        // dont allow zoom to reach zero or you get stuck
+        plan_view.height = plan_view.height.max(1.0);
+
+        *projection = Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedVertical(plan_view.height),
+            far: 2.0 * PLAN_VIEW_ALTITUDE,
+            ..Default::default()
+        });
+        *transform = Transform::from_translation(
+            plan_view.center.extend(global_resource.max_track_ht + PLAN_VIEW_ALTITUDE),
+        );
+    }
+
+    *was_active = true;
+}
+
+/// bounds in the XY plane of one track element including its width
+fn element_plan_bounds(centerline_resource: &CenterlineResource, element_index: i32) -> Option<(Vec2, Vec2)> {
+    let element_index = usize::try_from(element_index).ok()?;
+
+    centerline_resource
+        .points
+        .iter()
+        .filter(|point| point.element_index == element_index)
+        .fold(None, |bounds, point| {
+            let margin = Vec2::splat(0.5 * point.width);
+            let p = point.position.truncate();
+            Some(match bounds {
+                None => (p - margin, p + margin),
+                Some((min, max)) => (min.min(p - margin), max.max(p + margin)),
+            })
+        })
+}
+
+/// the pan/orbit camera drives the view in the editor orbit camera mode only
+pub fn orbit_camera_active(global_resource: &GlobalResource) -> bool {
+    global_resource.app_mode == AppMode::Editor && global_resource.camera_mode == CameraMode::Orbit
+}
+
+/// Keep the orbit view while another camera mode is active and put it back afterwards
+pub fn restore_orbit_camera(
+    global_resource: Res<GlobalResource>,
+    mut orbit_transform: Local<Option<Transform>>,
+    mut camera_query: Query<&mut Transform, With<PanOrbitCamera>>,
+) {
+    for mut transform in camera_query.iter_mut() {
+        if orbit_camera_active(&global_resource) {
+            if let Some(original) = orbit_transform.take() {
+                *transform = original;
+            }
+        } else if orbit_transform.is_none() {
+            *orbit_transform = Some(*transform);
+        }
+    }
+}
+
+/// Follow the player vehicle from behind in play mode
+pub fn chase_camera(
+    time: Res<Time>,
+    global_resource: Res<GlobalResource>,
+    vehicle_query: Query<&Transform, (With<Vehicle>, With<PlayerControlled>)>,
+    mut camera_query: Query<(&ChaseCamera, &mut Transform), Without<Vehicle>>,
+) {
+    if global_resource.app_mode != AppMode::Play {
+        return;
+    }
+
+    for (chase, mut transform) in camera_query.iter_mut() {
+        let Ok(vehicle_transform) = vehicle_query.get_single() else {
+            continue;
+        };
+
+        // heading in the ground plane so pitching and rolling don't swing the camera
+        let forward = (vehicle_transform.rotation * Vec3::X)
+            .truncate()
+            .normalize_or_zero()
+            .extend(0.);
+
+        let target = vehicle_transform.translation - forward * chase.distance
+            + Vec3::Z * chase.height;
+        let blend = 1. - (-chase.stiffness * time.delta_seconds()).exp();
+
+        transform.translation = transform.translation.lerp(target, blend);
+        transform.look_at(
+            vehicle_transform.translation + Vec3::Z * chase.look_height,
+            Vec3::Z,
+        );
+    }
+}
+
+/// Travel along the track centerline looking down the track tangent
+pub fn flythrough_camera(
+    time: Res<Time>,
+    global_resource: Res<GlobalResource>,
+    centerline_resource: Res<CenterlineResource>,
+    mut camera_query: Query<(&mut FlythroughCamera, &mut Transform)>,
+) {
+    if global_resource.app_mode != AppMode::Editor
+        || global_resource.camera_mode != CameraMode::Flythrough
+        || centerline_resource.points.len() < 2
+    {
+        return;
+    }
+
+    for (mut flythrough, mut transform) in camera_query.iter_mut() {
+        if flythrough.playing {
+            // playing again from the end of an open track starts over
+            if !centerline_resource.closed && flythrough.s >= centerline_resource.length {
+                flythrough.s = 0.;
+            }
+            let s = flythrough.s + flythrough.speed * time.delta_seconds();
+
+            // loops keep going, open tracks stop at the end
+            if !centerline_resource.closed && s >= centerline_resource.length {
+                flythrough.playing = false;
+            }
+            flythrough.s = centerline_resource.wrap(s);
+        }
+
+        let point = centerline_resource.sample(flythrough.s);
+
+        // banking rotates the track surface about the direction of travel
+        let up = if flythrough.bank_roll {
+            Quat::from_axis_angle(point.tangent, point.bank_angle.to_radians()) * Vec3::Z
+        } else {
+            Vec3::Z
+        };
+
+        *transform = Transform::from_translation(point.position + up * flythrough.height)
+            .looking_to(point.tangent, up);
+    }
+}
+
+/// Load the camera input settings, the defaults stay when there is no settings file yet
+pub fn load_camera_input(mut input_map: ResMut<CameraInputMap>) {
+    if let Ok(loaded) = CameraInputMap::load(CAMERA_SETTINGS_PATH) {
+        // loading isn't an edit, don't write the file straight back
+        *input_map.bypass_change_detection() = loaded;
+    }
+}
+
+/// Load the camera bookmarks of all track layouts
+pub fn load_camera_bookmarks(mut bookmark_resource: ResMut<CameraBookmarkResource>) {
+    if let Ok(loaded) = CameraBookmarkResource::load(CAMERA_BOOKMARKS_PATH) {
+        *bookmark_resource = loaded;
+    }
+}
+
+/// Keep the bookmarks of the track in the bookmarks file. Each layout shows the bookmarks made
+/// on it, the file is only written when the bookmarks themselves are edited.
+pub fn sync_camera_bookmarks(
+    centerline_resource: Res<CenterlineResource>,
+    mut track_resource: ResMut<TrackResource>,
+    mut bookmark_resource: ResMut<CameraBookmarkResource>,
+    mut rebind_resource: ResMut<CameraRebindResource>,
+) {
+    if !track_resource.is_changed() && !centerline_resource.is_changed() {
+        return;
+    }
+    // the hash is only known once the track has been spawned
+    if centerline_resource.points.is_empty() {
+        return;
+    }
+
+    // another layout, the stored bookmarks of every layout are left as they are
+    let layout_hash = centerline_resource.layout_hash;
+    if bookmark_resource.layout_hash != Some(layout_hash) {
+        bookmark_resource.layout_hash = Some(layout_hash);
+        let bookmarks = bookmark_resource
+            .bookmarks(layout_hash)
+            .cloned()
+            .unwrap_or_default();
+        if bookmarks != track_resource.camera_bookmarks {
+            track_resource.camera_bookmarks = bookmarks;
+        }
+        return;
+    }
+
+    let stored = bookmark_resource
+        .bookmarks(layout_hash)
+        .map_or(&[][..], |bookmarks| &bookmarks[..]);
+    if stored == &track_resource.camera_bookmarks[..] {
+        return;
+    }
+
+    bookmark_resource.set_bookmarks(layout_hash, &track_resource.camera_bookmarks);
+    if let Err(error) = bookmark_resource.save(CAMERA_BOOKMARKS_PATH) {
+        rebind_resource.status = format!("could not save bookmarks: {error}");
+    }
+}
+
+/// Write the camera input settings whenever they were edited
+pub fn save_camera_input(
+    input_map: Res<CameraInputMap>,
+    mut rebind_resource: ResMut<CameraRebindResource>,
+) {
+    if !input_map.is_changed() || input_map.is_added() {
+        return;
+    }
+
+    rebind_resource.status = match input_map.save(CAMERA_SETTINGS_PATH) {
+        Ok(()) => format!("saved to {CAMERA_SETTINGS_PATH}"),
+        Err(error) => format!("could not save settings: {error}"),
+    };
+}
+
+/// Bind the next key or mouse button to the action waiting for a binding. A modifier held with
+/// a mouse button binds the combination, escape cancels and delete clears the binding.
+pub fn capture_rebinding(
+    input_keys: Res<Input<KeyCode>>,
+    input_mouse: Res<Input<MouseButton>>,
+    mut rebind_resource: ResMut<CameraRebindResource>,
+    mut input_map: ResMut<CameraInputMap>,
+) {
+    let Some((action, slot)) = rebind_resource.pending else {
+        return;
+    };
+
+    if input_keys.just_pressed(KeyCode::Escape) {
+        rebind_resource.pending = None;
+        return;
+    }
+
+    let held_modifier = MODIFIER_KEYS.into_iter().find(|key| input_keys.pressed(*key));
+
+    let binding = if input_keys.just_pressed(KeyCode::Delete) || input_keys.just_pressed(KeyCode::Back) {
+        Some(InputBinding::Unbound)
+    } else if let Some(button) = MOUSE_BUTTONS.into_iter().find(|button| input_mouse.just_pressed(*button)) {
+        Some(match held_modifier {
+            Some(key) => InputBinding::ModifiedMouse(key, button),
+            None => InputBinding::Mouse(button),
+        })
+    } else if let Some(key) = BINDABLE_KEYS
+        .into_iter()
+        .find(|key| !MODIFIER_KEYS.contains(key) && input_keys.just_pressed(*key))
+    {
+        Some(InputBinding::Key(key))
+    } else {
+        // a modifier released without a mouse button binds the modifier on its own
+        MODIFIER_KEYS
+            .into_iter()
+            .find(|key| input_keys.just_released(*key))
+            .map(InputBinding::Key)
+    };
+
+    if let Some(binding) = binding {
+        input_map.set_binding(action, slot, binding);
+        rebind_resource.pending = None;
+    }
+}
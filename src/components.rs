@@ -1,8 +1,24 @@
 use bevy::prelude::*;
 
+use std::ops::Range;
+
+use crate::{
+    camera::components::CameraBookmark,
+    spline::components::ControlPoint,
+    track::{
+        banking::BankingParams,
+        compound::CompoundElement,
+        connect::{mirror_point, Connection},
+        systems::{element_start_transform, TRACK_START_POSITION},
+        vertical::{ElementProfile, VerticalCurve},
+    },
+};
+use std::f32::consts::PI;
+
 pub const TERRAIN_OFFSET: f32 = 25.; // how much the terrain extends past track
+pub const DEFAULT_TRACK_WIDTH: f32 = 8.; // ft, width of a generated section at the start of the track
 
-#[derive(Resource, Default)]
+#[derive(Resource, Default, Clone)]
 pub struct GlobalResource {
     // terrain parameters
     pub frequency_scale: f32,
@@ -28,22 +44,278 @@ pub struct GlobalResource {
     pub track_texture_scale: f32,
     pub terrain_texture_scale: f32,
     pub highlighted_track_index: i32,
+    pub camera_mode: CameraMode,
+
+    // play mode parameters
+    pub app_mode: AppMode,
+    pub autopilot: bool,
+    pub racing_line: bool,
 }
 
-#[derive(Resource, Default)]
+/// camera used in the editor, play mode always uses the chase camera
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CameraMode {
+    #[default]
+    Orbit,
+    Flythrough,
+    /// orthographic top-down view of the layout
+    PlanView,
+}
+
+/// editor is the default pan/orbit layout view, play drives a vehicle over the generated world
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AppMode {
+    #[default]
+    Editor,
+    Play,
+}
+
+#[derive(Resource)]
 pub struct TrackResource {
     pub track_list: Vec<TrackElement2D>,
     pub track_map_image_handle: Handle<Image>,
     pub track_texture_handle: Handle<Image>,
+    pub camera_bookmarks: Vec<CameraBookmark>,
+    /// parts of the track list generated from splines and compound elements, in track list order
+    pub sections: Vec<TrackSection>,
+    /// start of the first element in the world
+    pub start_transform: Transform,
+    /// smooths the grade changes between elements
+    pub vertical_curve: VerticalCurve,
+    /// banks the curves for a design speed whenever the track changes
+    pub banking: Option<BankingParams>,
+}
+
+impl Default for TrackResource {
+    fn default() -> TrackResource {
+        TrackResource {
+            track_list: vec![],
+            track_map_image_handle: default(),
+            track_texture_handle: default(),
+            camera_bookmarks: vec![],
+            sections: vec![],
+            start_transform: Transform::from_translation(TRACK_START_POSITION),
+            vertical_curve: VerticalCurve::default(),
+            banking: None,
+        }
+    }
+}
+
+impl TrackResource {
+    /// section the element at index belongs to
+    pub fn section_of(&self, index: usize) -> Option<usize> {
+        self.sections
+            .iter()
+            .position(|section| section.contains(index))
+    }
+
+    /// adds a section before index, or after the section index is in
+    pub fn insert_section(&mut self, index: usize, source: SectionSource) -> usize {
+        let mut start_index = index.min(self.track_list.len());
+        if let Some(section) = self.section_of(start_index) {
+            start_index = self.sections[section].elements().end;
+        }
+
+        let position = self
+            .sections
+            .partition_point(|section| section.start_index <= start_index);
+        self.sections.insert(
+            position,
+            TrackSection {
+                start_index,
+                element_count: 0,
+                source,
+            },
+        );
+        position
+    }
+
+    /// removes a section, its elements are kept as plain elements or removed with it
+    pub fn remove_section(&mut self, index: usize, remove_elements: bool) {
+        let section = self.sections.remove(index);
+        if remove_elements {
+            self.remove_elements(section.elements());
+        }
+    }
+
+    /// inserts elements before index, the sections after it move along and one it splits is
+    /// turned into plain elements
+    pub fn insert_elements(&mut self, index: usize, elements: &[TrackElement2D]) {
+        self.sections
+            .retain(|section| index <= section.start_index || index >= section.elements().end);
+        for section in &mut self.sections {
+            if section.start_index >= index {
+                section.start_index += elements.len();
+            }
+        }
+
+        self.track_list
+            .splice(index..index, elements.iter().copied());
+    }
+
+    /// removes the elements in range, the sections after it move along and the ones it overlaps
+    /// are turned into plain elements
+    pub fn remove_elements(&mut self, range: Range<usize>) {
+        self.retain_sections_outside(&range);
+        for section in &mut self.sections {
+            if section.start_index >= range.end {
+                section.start_index -= range.len();
+            }
+        }
+
+        self.track_list.drain(range);
+    }
+
+    /// swaps two elements, a section either belongs to is turned into plain elements
+    pub fn swap_elements(&mut self, a: usize, b: usize) {
+        self.sections
+            .retain(|section| !section.contains(a) && !section.contains(b));
+
+        self.track_list.swap(a, b);
+    }
+
+    /// flips the elements in range to turn and bank the other way, the sections it overlaps are
+    /// turned into plain elements
+    pub fn mirror_elements(&mut self, range: Range<usize>) {
+        self.retain_sections_outside(&range);
+
+        for track in &mut self.track_list[range] {
+            *track = track.mirrored();
+        }
+    }
+
+    /// reverses the order of the elements in range, their banking is reversed with them so it
+    /// stays continuous. The sections it overlaps are turned into plain elements.
+    pub fn reverse_elements(&mut self, range: Range<usize>) {
+        self.retain_sections_outside(&range);
+
+        let elements = &mut self.track_list[range];
+        elements.reverse();
+        for track in elements {
+            std::mem::swap(&mut track.start_bank_angle, &mut track.end_bank_angle);
+        }
+    }
+
+    /// mirrors the whole layout across the line the track starts along
+    pub fn mirror_layout(&mut self) {
+        for track in &mut self.track_list {
+            *track = track.mirrored();
+        }
+        for section in &mut self.sections {
+            section.source = section.source.mirrored(&self.start_transform);
+        }
+    }
+
+    /// drives the layout the other way round, the physical track is unchanged. Sections are
+    /// turned into plain elements, their sources describe the track in the old direction.
+    pub fn reverse_direction(&mut self) {
+        let end = element_start_transform(
+            &self.start_transform,
+            &self.track_list,
+            self.track_list.len(),
+        );
+        self.start_transform = end * Transform::from_rotation(Quat::from_rotation_z(PI));
+
+        self.sections.clear();
+        self.track_list.reverse();
+        for track in &mut self.track_list {
+            // the turns go the other way, the raised edge is now on the other side and climbs
+            // are descents
+            *track = TrackElement2D {
+                curvature: -track.curvature,
+                grade: -track.grade,
+                start_bank_angle: -track.end_bank_angle,
+                end_bank_angle: -track.start_bank_angle,
+                ..*track
+            };
+        }
+    }
+
+    fn retain_sections_outside(&mut self, range: &Range<usize>) {
+        self.sections.retain(|section| {
+            section.start_index >= range.end || section.elements().end <= range.start
+        });
+    }
+}
+
+/// part of the track list generated from a spline, a compound element or a connection, its elements are
+/// regenerated whenever the source or the elements before it change
+#[derive(Clone, PartialEq, Debug)]
+pub struct TrackSection {
+    /// first element of the section in the track list
+    pub start_index: usize,
+    pub element_count: usize,
+    pub source: SectionSource,
+}
+
+impl TrackSection {
+    pub fn elements(&self) -> Range<usize> {
+        self.start_index..self.start_index + self.element_count
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.elements().contains(&index)
+    }
+
+    pub fn control_points(&self) -> Option<&Vec<ControlPoint>> {
+        match &self.source {
+            SectionSource::Spline(control_points) => Some(control_points),
+            _ => None,
+        }
+    }
+
+    pub fn control_points_mut(&mut self) -> Option<&mut Vec<ControlPoint>> {
+        match &mut self.source {
+            SectionSource::Spline(control_points) => Some(control_points),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SectionSource {
+    /// smooth curve through control points
+    Spline(Vec<ControlPoint>),
+    Compound(CompoundElement),
+    /// reaches a target pose from the elements before it
+    Connect(Connection),
+}
+
+impl SectionSource {
+    /// source of the same section mirrored across the line the track starts along
+    pub fn mirrored(&self, start_transform: &Transform) -> SectionSource {
+        match self {
+            SectionSource::Spline(control_points) => SectionSource::Spline(
+                control_points
+                    .iter()
+                    .map(|control_point| ControlPoint {
+                        position: mirror_point(start_transform, control_point.position),
+                        bank_angle: control_point.bank_angle.map(|bank_angle| -bank_angle),
+                        ..*control_point
+                    })
+                    .collect(),
+            ),
+            SectionSource::Compound(compound) => SectionSource::Compound(compound.mirrored()),
+            SectionSource::Connect(connection) => {
+                SectionSource::Connect(connection.mirrored(start_transform))
+            }
+        }
+    }
 }
 
 #[derive(Resource, Default)]
 pub struct MeshResource {
     pub track_mesh_list: Vec<Handle<Mesh>>,
     pub track_mesh_transform_list: Vec<Transform>,
+    /// parameters each mesh was generated from, elements that differ are regenerated
+    pub track_element_list: Vec<TrackElement2D>,
+    pub track_profile_list: Vec<ElementProfile>,
+    /// start the transforms were laid out from
+    pub start_transform: Transform,
+    pub track_material: Handle<StandardMaterial>,
 }
 
-#[derive(Component, Default)]
+#[derive(Component, Default, Clone, Copy, PartialEq, Debug)]
 pub struct TrackElement2D {
     pub curvature: f32,
     pub curve_angle: f32,
@@ -51,6 +323,67 @@ pub struct TrackElement2D {
     pub end_bank_angle: f32,
     pub width: f32,
     pub length: f32,
+    /// percent the element climbs by along its length, negative downhill
+    pub grade: f32,
+    pub bank_pivot: BankPivot,
+}
+
+/// line along the element the bank rotates the track about, it stays at the height of the element
+/// tangent
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum BankPivot {
+    /// the edge on the inside of the curve, the edge the bank lowers on a straight
+    #[default]
+    InsideEdge,
+    Centerline,
+    /// the edge on the outside of the curve, the edge the bank raises on a straight
+    OutsideEdge,
+}
+
+impl BankPivot {
+    pub const ALL: [BankPivot; 3] = [
+        BankPivot::InsideEdge,
+        BankPivot::Centerline,
+        BankPivot::OutsideEdge,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BankPivot::InsideEdge => "Inside Edge",
+            BankPivot::Centerline => "Centerline",
+            BankPivot::OutsideEdge => "Outside Edge",
+        }
+    }
+
+    /// height of the centerline above the pivot for a track of width banked by bank_angle
+    /// (radians) on an element of curvature
+    pub fn centerline_height(&self, width: f32, bank_angle: f32, curvature: f32) -> f32 {
+        // side of the inside edge, 1 on the left
+        let inside = if curvature != 0. {
+            curvature.signum()
+        } else {
+            -bank_angle.sin().signum()
+        };
+        let inside_height = -inside * width / 2. * bank_angle.sin();
+
+        match self {
+            BankPivot::InsideEdge => inside_height,
+            BankPivot::Centerline => 0.,
+            BankPivot::OutsideEdge => -inside_height,
+        }
+    }
+}
+
+impl TrackElement2D {
+    /// the same element turning and banking the other way
+    pub fn mirrored(&self) -> TrackElement2D {
+        TrackElement2D {
+            curvature: -self.curvature,
+            start_bank_angle: -self.start_bank_angle,
+            end_bank_angle: -self.end_bank_angle,
+            ..*self
+        }
+    }
 }
 
 // labels
@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{GlobalResource, TrackElement2D, TrackResource, TrackSection},
+    track::{banking::BankingParams, vertical::VerticalCurve},
+};
+
+pub const MAX_HISTORY: usize = 200; // oldest edits are dropped past this
+pub const MERGE_WINDOW: f64 = 0.5; // s, edits of the same kind closer than this are one step
+
+/// terrain parameters covered by the edit history
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct TerrainParams {
+    pub frequency_scale: f32,
+    pub amplitude_scale: f32,
+    pub octaves: usize,
+    pub lacunarity: f32,
+    pub gain: f32,
+    pub scale: f32,
+    pub track_texture_scale: f32,
+    pub terrain_texture_scale: f32,
+}
+
+impl TerrainParams {
+    pub fn from_global(global_resource: &GlobalResource) -> TerrainParams {
+        TerrainParams {
+            frequency_scale: global_resource.frequency_scale,
+            amplitude_scale: global_resource.amplitude_scale,
+            octaves: global_resource.octaves,
+            lacunarity: global_resource.lacunarity,
+            gain: global_resource.gain,
+            scale: global_resource.scale,
+            track_texture_scale: global_resource.track_texture_scale,
+            terrain_texture_scale: global_resource.terrain_texture_scale,
+        }
+    }
+
+    pub fn apply(&self, global_resource: &mut GlobalResource) {
+        global_resource.frequency_scale = self.frequency_scale;
+        global_resource.amplitude_scale = self.amplitude_scale;
+        global_resource.octaves = self.octaves;
+        global_resource.lacunarity = self.lacunarity;
+        global_resource.gain = self.gain;
+        global_resource.scale = self.scale;
+        global_resource.track_texture_scale = self.track_texture_scale;
+        global_resource.terrain_texture_scale = self.terrain_texture_scale;
+    }
+}
+
+/// track list with the sections generated into it, where it starts and how it is banked
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct TrackLayout {
+    pub track_list: Vec<TrackElement2D>,
+    pub sections: Vec<TrackSection>,
+    pub start_transform: Transform,
+    pub vertical_curve: VerticalCurve,
+    pub banking: Option<BankingParams>,
+}
+
+impl TrackLayout {
+    pub fn from_track(track_resource: &TrackResource) -> TrackLayout {
+        TrackLayout {
+            track_list: track_resource.track_list.clone(),
+            sections: track_resource.sections.clone(),
+            start_transform: track_resource.start_transform,
+            vertical_curve: track_resource.vertical_curve,
+            banking: track_resource.banking,
+        }
+    }
+
+    pub fn apply(&self, track_resource: &mut TrackResource) {
+        track_resource.track_list.clone_from(&self.track_list);
+        track_resource.sections.clone_from(&self.sections);
+        track_resource.start_transform = self.start_transform;
+        track_resource.vertical_curve = self.vertical_curve;
+        track_resource.banking = self.banking;
+    }
+
+    /// same as the track resource, without cloning it
+    pub fn matches(&self, track_resource: &TrackResource) -> bool {
+        self.track_list == track_resource.track_list
+            && self.sections == track_resource.sections
+            && self.start_transform == track_resource.start_transform
+            && self.vertical_curve == track_resource.vertical_curve
+            && self.banking == track_resource.banking
+    }
+}
+
+/// one reversible edit, holds the state on both sides of it
+#[derive(Clone, Debug)]
+pub enum EditCommand {
+    TrackList {
+        before: TrackLayout,
+        after: TrackLayout,
+    },
+    Terrain {
+        before: TerrainParams,
+        after: TerrainParams,
+    },
+}
+
+impl EditCommand {
+    /// state before the edit
+    pub fn undo(&self, track_resource: &mut TrackResource, global_resource: &mut GlobalResource) {
+        match self {
+            EditCommand::TrackList { before, .. } => before.apply(track_resource),
+            EditCommand::Terrain { before, .. } => before.apply(global_resource),
+        }
+    }
+
+    /// state after the edit
+    pub fn redo(&self, track_resource: &mut TrackResource, global_resource: &mut GlobalResource) {
+        match self {
+            EditCommand::TrackList { after, .. } => after.apply(track_resource),
+            EditCommand::Terrain { after, .. } => after.apply(global_resource),
+        }
+    }
+
+    /// extends this edit to end where the next one ends, true if they were the same kind
+    pub fn merge(&mut self, next: &EditCommand) -> bool {
+        match (self, next) {
+            (EditCommand::TrackList { after, .. }, EditCommand::TrackList { after: next, .. }) => {
+                after.clone_from(next);
+                true
+            }
+            (EditCommand::Terrain { after, .. }, EditCommand::Terrain { after: next, .. }) => {
+                *after = *next;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub label: String,
+    pub command: EditCommand,
+}
+
+/// requested from the history panel, applied by apply_history
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HistoryAction {
+    Undo,
+    Redo,
+    /// undo or redo until this many edits are applied
+    JumpTo(usize),
+}
+
+/// edit history of the track list and terrain parameters
+#[derive(Resource, Default)]
+pub struct HistoryResource {
+    pub undo_stack: Vec<HistoryEntry>,
+    pub redo_stack: Vec<HistoryEntry>,
+    pub action: Option<HistoryAction>,
+
+    // last recorded state, edits are the difference to it
+    pub layout: TrackLayout,
+    pub terrain: TerrainParams,
+    pub initialized: bool,
+    pub last_edit_time: f64,
+}
+
+impl HistoryResource {
+    /// records an edit, merging it into the previous one when it continues it
+    pub fn push(&mut self, label: String, command: EditCommand, time: f64) {
+        self.redo_stack.clear();
+
+        let merged = time - self.last_edit_time < MERGE_WINDOW
+            && self
+                .undo_stack
+                .last_mut()
+                .is_some_and(|entry| entry.label == label && entry.command.merge(&command));
+        self.last_edit_time = time;
+        if merged {
+            return;
+        }
+
+        self.undo_stack.push(HistoryEntry { label, command });
+        if self.undo_stack.len() > MAX_HISTORY {
+            self.undo_stack.remove(0);
+        }
+    }
+}
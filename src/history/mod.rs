@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+
+pub mod components;
+mod systems;
+
+use components::*;
+use systems::*;
+
+use crate::track::systems::update_banking;
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HistoryResource>()
+            .add_system(apply_history)
+            .add_system(
+                record_edits
+                    .after(apply_history)
+                    .after(update_banking),
+            );
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+
+use crate::components::*;
+use crate::history::components::*;
+
+/// bevy system
+/// ctrl+z undoes, ctrl+shift+z redoes, and applies the actions requested from the history panel
+pub fn apply_history(
+    keys: Res<Input<KeyCode>>,
+    mut global_resource: ResMut<GlobalResource>,
+    mut track_resource: ResMut<TrackResource>,
+    mut history_resource: ResMut<HistoryResource>,
+    mut contexts: EguiContexts,
+) {
+    // text fields have their own undo
+    if !contexts.ctx_mut().wants_keyboard_input()
+        && keys.any_pressed([KeyCode::LControl, KeyCode::RControl])
+        && keys.just_pressed(KeyCode::Z)
+    {
+        history_resource.action = if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+            Some(HistoryAction::Redo)
+        } else {
+            Some(HistoryAction::Undo)
+        };
+    }
+
+    let Some(action) = history_resource.action.take() else {
+        return;
+    };
+
+    let target = match action {
+        HistoryAction::Undo => history_resource.undo_stack.len().saturating_sub(1),
+        HistoryAction::Redo => history_resource.undo_stack.len() + 1,
+        HistoryAction::JumpTo(target) => target,
+    };
+
+    let history_resource = &mut *history_resource;
+    while history_resource.undo_stack.len() > target {
+        let Some(entry) = history_resource.undo_stack.pop() else {
+            break;
+        };
+        entry
+            .command
+            .undo(&mut track_resource, &mut global_resource);
+        history_resource.redo_stack.push(entry);
+    }
+    while history_resource.undo_stack.len() < target {
+        let Some(entry) = history_resource.redo_stack.pop() else {
+            break;
+        };
+        entry
+            .command
+            .redo(&mut track_resource, &mut global_resource);
+        history_resource.undo_stack.push(entry);
+    }
+
+    // the restored state is not a new edit and the next edit starts a new step
+    history_resource.layout = TrackLayout::from_track(&track_resource);
+    history_resource.terrain = TerrainParams::from_global(&global_resource);
+    history_resource.last_edit_time = f64::NEG_INFINITY;
+
+    // the selection may point past the end of a shorter list
+    if global_resource.highlighted_track_index >= track_resource.track_list.len() as i32 {
+        global_resource.highlighted_track_index = -1;
+    }
+}
+
+/// bevy system
+/// compares the track list and terrain parameters to the last recorded state and records any
+/// difference as an edit
+pub fn record_edits(
+    time: Res<Time>,
+    global_resource: Res<GlobalResource>,
+    track_resource: Res<TrackResource>,
+    mut history_resource: ResMut<HistoryResource>,
+) {
+    let terrain = TerrainParams::from_global(&global_resource);
+
+    // the state the app starts with is not undoable
+    if !history_resource.initialized {
+        history_resource.layout = TrackLayout::from_track(&track_resource);
+        history_resource.terrain = terrain;
+        history_resource.initialized = true;
+        return;
+    }
+
+    if !history_resource.layout.matches(&track_resource) {
+        let before = std::mem::take(&mut history_resource.layout);
+        let after = TrackLayout::from_track(&track_resource);
+        let label = if before.vertical_curve != after.vertical_curve {
+            String::from("Edit vertical curves")
+        } else if before.banking != after.banking {
+            String::from("Edit banking")
+        // the start only moves when the direction is reversed
+        } else if before.start_transform != after.start_transform
+            && before.track_list.len() == after.track_list.len()
+        {
+            String::from("Reverse direction")
+        } else {
+            section_edit_label(&before.sections, &after.sections)
+                .unwrap_or_else(|| track_edit_label(&before.track_list, &after.track_list))
+        };
+
+        history_resource.layout = after.clone();
+        history_resource.push(
+            label,
+            EditCommand::TrackList { before, after },
+            time.elapsed_seconds_f64(),
+        );
+    }
+
+    if terrain != history_resource.terrain {
+        let before = history_resource.terrain;
+        history_resource.terrain = terrain;
+        history_resource.push(
+            String::from("Edit terrain"),
+            EditCommand::Terrain {
+                before,
+                after: terrain,
+            },
+            time.elapsed_seconds_f64(),
+        );
+    }
+}
+
+/// history panel text for a change of a section, sections moving along with inserted or removed
+/// elements aren't section edits
+pub fn section_edit_label(before: &[TrackSection], after: &[TrackSection]) -> Option<String> {
+    if before.len() != after.len() {
+        return None;
+    }
+
+    let changed = before
+        .iter()
+        .zip(after)
+        .find(|(before, after)| before.source != after.source)?;
+    Some(match &changed.1.source {
+        SectionSource::Spline(_) => String::from("Edit spline"),
+        SectionSource::Compound(compound) => format!("Edit {}", compound.name()),
+        SectionSource::Connect(_) => String::from("Edit connection"),
+    })
+}
+
+/// history panel text for a change of the track list
+pub fn track_edit_label(before: &[TrackElement2D], after: &[TrackElement2D]) -> String {
+    let first_change = before
+        .iter()
+        .zip(after)
+        .position(|(before, after)| before != after)
+        .unwrap_or(before.len().min(after.len()));
+
+    if after.len() == before.len() + 1 {
+        return format!("Add element {first_change}");
+    }
+    if after.len() + 1 == before.len() {
+        return format!("Delete element {first_change}");
+    }
+    if after.len() != before.len() {
+        return String::from("Edit track");
+    }
+
+    let changed: Vec<usize> = (0..after.len())
+        .filter(|index| before[*index] != after[*index])
+        .collect();
+    match changed[..] {
+        [index] => format!("Edit element {index}"),
+        [first, second] if before[first] == after[second] && before[second] == after[first] => {
+            format!("Swap elements {first} and {second}")
+        }
+        _ => String::from("Edit track"),
+    }
+}
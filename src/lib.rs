@@ -0,0 +1,19 @@
+pub mod ai_driver;
+pub mod camera;
+pub mod history;
+pub mod profile;
+pub mod replay;
+pub mod selection;
+pub mod spline;
+pub mod terrain;
+pub mod timing;
+pub mod tools;
+pub mod track;
+pub mod track_height_map;
+pub mod ui;
+pub mod vehicle;
+
+pub mod components;
+pub mod systems;
+
+use components::*;
@@ -1,21 +1,10 @@
-pub mod camera;
-pub mod terrain;
-pub mod tools;
-pub mod track;
-pub mod track_height_map;
-pub mod ui;
-
-pub mod components;
-mod systems;
-
-use components::*;
-use systems::*;
-
-use camera::CameraPlugin;
-use terrain::TerrainPlugin;
-use track::TrackPlugin;
-use track_height_map::TrackHeightMapPlugin;
-use ui::UIPlugin;
+use race_track::{
+    ai_driver::AiDriverPlugin, camera::CameraPlugin, components::*, history::HistoryPlugin,
+    profile::ProfilePlugin, replay::ReplayPlugin, selection::SelectionPlugin,
+    spline::SplinePlugin, systems::*, terrain::TerrainPlugin, timing::TimingPlugin,
+    track::TrackPlugin, track_height_map::TrackHeightMapPlugin, ui::UIPlugin,
+    vehicle::VehiclePlugin,
+};
 
 use bevy::prelude::*;
 
@@ -35,5 +24,13 @@ fn main() {
         .add_plugin(TrackHeightMapPlugin)
         .add_plugin(TerrainPlugin)
         .add_plugin(UIPlugin)
+        .add_plugin(VehiclePlugin)
+        .add_plugin(AiDriverPlugin)
+        .add_plugin(TimingPlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(SelectionPlugin)
+        .add_plugin(HistoryPlugin)
+        .add_plugin(ProfilePlugin)
+        .add_plugin(SplinePlugin)
         .run();
 }
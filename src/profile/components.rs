@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+pub const PROFILE_CURSOR_RADIUS: f32 = 0.5; // ft
+pub const PROFILE_PLOT_HEIGHT: f32 = 110.; // px
+
+/// arc length shown by the cursor of the profile plots and the marker on the track
+#[derive(Resource, Default)]
+pub struct ProfileResource {
+    pub cursor_s: Option<f32>,
+    /// the cursor follows the plots instead of the 3d view while they are hovered
+    pub plot_hovered: bool,
+}
+
+// labels
+// marker on the track at the profile cursor
+#[derive(Component)]
+pub struct ProfileCursor;
@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+pub mod components;
+mod systems;
+
+use components::*;
+use systems::*;
+
+use crate::track::systems::update_centerline;
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProfileResource>()
+            .add_startup_system(setup)
+            .add_system(track_profile_cursor.after(update_centerline))
+            .add_system(update_profile_cursor.after(track_profile_cursor));
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_egui::EguiContexts;
+
+use crate::{
+    camera::components::PanOrbitCamera,
+    components::*,
+    profile::components::*,
+    tools::raycast::{raycast_mesh, RayHit},
+    track::components::CenterlineResource,
+};
+
+pub fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // hidden until the cursor is on the track or a plot
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::UVSphere {
+                radius: PROFILE_CURSOR_RADIUS,
+                ..default()
+            })),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgb(1.0, 1.0, 0.2),
+                unlit: true,
+                ..default()
+            }),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        ProfileCursor,
+    ));
+}
+
+/// moves the profile cursor to the point of the track under the mouse in the 3d view
+#[allow(clippy::too_many_arguments)]
+pub fn track_profile_cursor(
+    mut cursor_moved: EventReader<CursorMoved>,
+    meshes: Res<Assets<Mesh>>,
+    global_resource: Res<GlobalResource>,
+    mesh_resource: Res<MeshResource>,
+    centerline_resource: Res<CenterlineResource>,
+    mut profile_resource: ResMut<ProfileResource>,
+    mut contexts: EguiContexts,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+) {
+    // raycasting is only redone when the mouse moves
+    let moved = cursor_moved.iter().count() > 0;
+    if !moved
+        || global_resource.app_mode != AppMode::Editor
+        || profile_resource.plot_hovered
+        || contexts.ctx_mut().is_pointer_over_area()
+    {
+        return;
+    }
+
+    let (Ok(window), Ok((camera, camera_transform))) =
+        (window_query.get_single(), camera_query.get_single())
+    else {
+        return;
+    };
+    let Some(ray) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+    else {
+        return;
+    };
+
+    let mut closest: Option<RayHit> = None;
+
+    for (mesh_handle, transform) in mesh_resource
+        .track_mesh_list
+        .iter()
+        .zip(mesh_resource.track_mesh_transform_list.iter())
+    {
+        let Some(mesh) = meshes.get(mesh_handle) else {
+            continue;
+        };
+        if let Some(hit) = raycast_mesh(mesh, transform, ray.origin, ray.direction) {
+            if closest.is_none_or(|closest| hit.distance < closest.distance) {
+                closest = Some(hit);
+            }
+        }
+    }
+
+    let cursor_s = closest
+        .and_then(|hit| centerline_resource.closest_index(hit.position))
+        .map(|index| centerline_resource.points[index].s);
+    if profile_resource.cursor_s != cursor_s {
+        profile_resource.cursor_s = cursor_s;
+    }
+}
+
+/// places the marker on the centerline at the profile cursor
+pub fn update_profile_cursor(
+    global_resource: Res<GlobalResource>,
+    centerline_resource: Res<CenterlineResource>,
+    profile_resource: Res<ProfileResource>,
+    mut cursor_query: Query<(&mut Transform, &mut Visibility), With<ProfileCursor>>,
+) {
+    let Ok((mut transform, mut visibility)) = cursor_query.get_single_mut() else {
+        return;
+    };
+
+    let cursor_s = profile_resource.cursor_s.filter(|_| {
+        global_resource.app_mode == AppMode::Editor && !centerline_resource.points.is_empty()
+    });
+
+    match cursor_s {
+        Some(s) => {
+            let point = centerline_resource.sample(s);
+            transform.translation = point.position + Vec3::Z * PROFILE_CURSOR_RADIUS;
+            if *visibility != Visibility::Visible {
+                *visibility = Visibility::Visible;
+            }
+        }
+        None => {
+            if *visibility != Visibility::Hidden {
+                *visibility = Visibility::Hidden;
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use bevy::prelude::*;
+use std::{fmt, fs, io, path::Path};
+
+// replay file layout, little endian:
+//   magic (4 bytes), version (u16), track layout hash (u64), tick period (f32), frame count (u32)
+//   per frame: position (3 x f32), rotation quantized to 4 x i16
+pub const REPLAY_MAGIC: [u8; 4] = *b"RTRP";
+pub const REPLAY_VERSION: u16 = 1;
+pub const REPLAY_HEADER_SIZE: usize = 22;
+pub const REPLAY_FRAME_SIZE: usize = 20;
+pub const DEFAULT_REPLAY_PATH: &str = "replays/replay.rtr";
+
+/// pose of a vehicle at one tick
+#[derive(Clone, Copy, Debug)]
+pub struct ReplayFrame {
+    pub position: Vec3,
+    pub rotation: Quat,
+}
+
+/// vehicle poses recorded every fixed tick on one track layout
+#[derive(Clone, Default)]
+pub struct Replay {
+    pub layout_hash: u64,
+    pub tick_period: f32,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    InvalidFormat,
+    UnsupportedVersion(u16),
+    /// the replay was recorded on a different track layout
+    StaleLayout,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(error) => write!(f, "{error}"),
+            ReplayError::InvalidFormat => write!(f, "not a replay file"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "unsupported replay version {version}")
+            }
+            ReplayError::StaleLayout => write!(f, "replay was recorded on a different track"),
+        }
+    }
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(error: io::Error) -> Self {
+        ReplayError::Io(error)
+    }
+}
+
+impl Replay {
+    pub fn new(layout_hash: u64, tick_period: f32) -> Replay {
+        Replay {
+            layout_hash,
+            tick_period,
+            frames: vec![],
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.frames.len().saturating_sub(1) as f32 * self.tick_period
+    }
+
+    /// interpolated pose at time since the start of the replay
+    pub fn pose_at(&self, time: f32) -> Option<Transform> {
+        let last = self.frames.len().checked_sub(1)?;
+
+        let position = (time / self.tick_period).clamp(0., last as f32);
+        let index = (position.floor() as usize).min(last);
+        let next = (index + 1).min(last);
+        let fraction = position - index as f32;
+
+        let a = &self.frames[index];
+        let b = &self.frames[next];
+
+        Some(Transform {
+            translation: a.position.lerp(b.position, fraction),
+            rotation: a.rotation.slerp(b.rotation, fraction),
+            ..default()
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(REPLAY_HEADER_SIZE + self.frames.len() * REPLAY_FRAME_SIZE);
+
+        bytes.extend_from_slice(&REPLAY_MAGIC);
+        bytes.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.layout_hash.to_le_bytes());
+        bytes.extend_from_slice(&self.tick_period.to_le_bytes());
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+
+        for frame in &self.frames {
+            for value in frame.position.to_array() {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            for value in frame.rotation.normalize().to_array() {
+                let quantized = (value * i16::MAX as f32).round() as i16;
+                bytes.extend_from_slice(&quantized.to_le_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        if bytes.len() < REPLAY_HEADER_SIZE || bytes[0..4] != REPLAY_MAGIC {
+            return Err(ReplayError::InvalidFormat);
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        let layout_hash = u64::from_le_bytes(bytes[6..14].try_into().unwrap());
+        let tick_period = f32::from_le_bytes(bytes[14..18].try_into().unwrap());
+        let frame_count = u32::from_le_bytes(bytes[18..22].try_into().unwrap()) as usize;
+
+        let frame_bytes = &bytes[REPLAY_HEADER_SIZE..];
+        if frame_bytes.len() != frame_count * REPLAY_FRAME_SIZE || tick_period <= 0. {
+            return Err(ReplayError::InvalidFormat);
+        }
+
+        let read_f32 = |chunk: &[u8], offset: usize| {
+            f32::from_le_bytes(chunk[offset..offset + 4].try_into().unwrap())
+        };
+        let read_i16 = |chunk: &[u8], offset: usize| {
+            i16::from_le_bytes([chunk[offset], chunk[offset + 1]]) as f32 / i16::MAX as f32
+        };
+
+        let frames = frame_bytes
+            .chunks_exact(REPLAY_FRAME_SIZE)
+            .map(|chunk| ReplayFrame {
+                position: Vec3::new(read_f32(chunk, 0), read_f32(chunk, 4), read_f32(chunk, 8)),
+                rotation: Quat::from_xyzw(
+                    read_i16(chunk, 12),
+                    read_i16(chunk, 14),
+                    read_i16(chunk, 16),
+                    read_i16(chunk, 18),
+                )
+                .normalize(),
+            })
+            .collect();
+
+        Ok(Replay {
+            layout_hash,
+            tick_period,
+            frames,
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), ReplayError> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    /// loads a replay, rejecting it unless it was recorded on the layout with layout_hash
+    pub fn load(path: &str, layout_hash: u64) -> Result<Replay, ReplayError> {
+        let replay = Replay::from_bytes(&fs::read(path)?)?;
+
+        if replay.layout_hash != layout_hash {
+            return Err(ReplayError::StaleLayout);
+        }
+
+        Ok(replay)
+    }
+}
+
+/// records the pose of its vehicle every fixed tick
+#[derive(Component, Default)]
+pub struct ReplayRecorder {
+    pub replay: Replay,
+}
+
+// labels
+#[derive(Component)]
+pub struct Ghost;
+
+#[derive(Resource)]
+pub struct ReplayResource {
+    pub file_path: String,
+    /// record the vehicles, ai driven ones included
+    pub recording: bool,
+    pub last_recording: Option<Replay>,
+
+    // ghost playback
+    pub ghost: Option<Replay>,
+    pub playing: bool,
+    pub time: f32,
+    pub speed: f32,
+
+    pub status: String,
+}
+
+impl Default for ReplayResource {
+    fn default() -> Self {
+        ReplayResource {
+            file_path: String::from(DEFAULT_REPLAY_PATH),
+            recording: false,
+            last_recording: None,
+
+            ghost: None,
+            playing: true,
+            time: 0.,
+            speed: 1.,
+
+            status: String::new(),
+        }
+    }
+}
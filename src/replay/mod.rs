@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+
+pub mod components;
+pub mod systems;
+
+use components::*;
+use systems::*;
+
+use crate::vehicle::systems::vehicle_physics;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayResource>()
+            .add_system(sync_recorder)
+            .add_system(reject_stale_replays)
+            .add_system(update_ghost)
+            .add_system(
+                record_frames
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .after(vehicle_physics),
+            );
+    }
+}
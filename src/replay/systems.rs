@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+
+use crate::{
+    ai_driver::components::AiDriver,
+    components::*,
+    replay::components::*,
+    track::components::*,
+    vehicle::components::{PlayerControlled, Vehicle},
+};
+
+/// starts and stops recording every vehicle to match the record option, whether it is driven by
+/// the player or an ai driver. The recording of the player vehicle is kept when there are several.
+#[allow(clippy::type_complexity)]
+pub fn sync_recorder(
+    mut commands: Commands,
+    fixed_time: Res<FixedTime>,
+    centerline_resource: Res<CenterlineResource>,
+    mut replay_resource: ResMut<ReplayResource>,
+    vehicle_query: Query<
+        (
+            Entity,
+            Option<&ReplayRecorder>,
+            Option<&AiDriver>,
+            Option<&PlayerControlled>,
+        ),
+        With<Vehicle>,
+    >,
+) {
+    // the vehicle and its recording are gone once play mode ends
+    if vehicle_query.is_empty() && replay_resource.recording {
+        replay_resource.recording = false;
+    }
+
+    let recording = replay_resource.recording;
+    let mut kept_player = false;
+
+    for (entity, recorder, ai_driver, player) in vehicle_query.iter() {
+        match (recording, recorder) {
+            (true, None) => {
+                commands.entity(entity).insert(ReplayRecorder {
+                    replay: Replay::new(
+                        centerline_resource.layout_hash,
+                        fixed_time.period.as_secs_f32(),
+                    ),
+                });
+            }
+            (false, Some(recorder)) => {
+                if !kept_player {
+                    let driver = if ai_driver.is_some() { "ai" } else { "player" };
+                    replay_resource.status = format!(
+                        "recorded {:.1} s, {} driven",
+                        recorder.replay.duration(),
+                        driver
+                    );
+                    replay_resource.last_recording = Some(recorder.replay.clone());
+                    kept_player = player.is_some();
+                }
+                commands.entity(entity).remove::<ReplayRecorder>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// appends the pose of each recorded vehicle, runs on the fixed timestep after the physics
+pub fn record_frames(mut recorder_query: Query<(&Transform, &mut ReplayRecorder)>) {
+    for (transform, mut recorder) in recorder_query.iter_mut() {
+        recorder.replay.frames.push(ReplayFrame {
+            position: transform.translation,
+            rotation: transform.rotation,
+        });
+    }
+}
+
+/// drops ghosts and recordings that no longer match the spawned track
+pub fn reject_stale_replays(
+    centerline_resource: Res<CenterlineResource>,
+    mut replay_resource: ResMut<ReplayResource>,
+) {
+    if !centerline_resource.is_changed() {
+        return;
+    }
+
+    let layout_hash = centerline_resource.layout_hash;
+
+    if replay_resource
+        .ghost
+        .as_ref()
+        .is_some_and(|ghost| ghost.layout_hash != layout_hash)
+    {
+        replay_resource.ghost = None;
+        replay_resource.status = String::from("ghost removed, the track layout changed");
+    }
+
+    if replay_resource
+        .last_recording
+        .as_ref()
+        .is_some_and(|recording| recording.layout_hash != layout_hash)
+    {
+        replay_resource.last_recording = None;
+    }
+}
+
+/// spawns the ghost in play mode while a ghost replay is loaded and moves it along the replay
+pub fn update_ghost(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+    global_resource: Res<GlobalResource>,
+    mut replay_resource: ResMut<ReplayResource>,
+    mut ghost_query: Query<(Entity, &mut Transform), With<Ghost>>,
+) {
+    let show_ghost = global_resource.app_mode == AppMode::Play && replay_resource.ghost.is_some();
+
+    if !show_ghost {
+        for (entity, _) in ghost_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    // advance and loop playback
+    let duration = replay_resource
+        .ghost
+        .as_ref()
+        .map_or(0., |ghost| ghost.duration());
+    if replay_resource.playing && duration > 0. {
+        let speed = replay_resource.speed;
+        replay_resource.time =
+            (replay_resource.time + time.delta_seconds() * speed).rem_euclid(duration);
+    }
+
+    let Some(pose) = replay_resource
+        .ghost
+        .as_ref()
+        .and_then(|ghost| ghost.pose_at(replay_resource.time))
+    else {
+        return;
+    };
+
+    if let Ok((_, mut transform)) = ghost_query.get_single_mut() {
+        *transform = pose;
+        return;
+    }
+
+    // same body as a vehicle, see-through
+    let body_size = Vehicle::default().body_size;
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Box::new(
+                body_size.x,
+                body_size.y,
+                body_size.z,
+            ))),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgba(0.2, 0.6, 1.0, 0.4),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            transform: pose,
+            ..default()
+        },
+        Ghost,
+    ));
+}
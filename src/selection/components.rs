@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+
+pub const HIGHLIGHT_LIFT: f32 = 0.05; // raise the highlight above the track surface to avoid z-fighting
+pub const HANDLE_RADIUS: f32 = 0.6; // ft
+
+// parameter limits, same as the track list drag values
+pub const HANDLE_MAX_CURVATURE: f32 = 2.;
+pub const HANDLE_MAX_CURVE_ANGLE: f32 = 190.;
+pub const HANDLE_MAX_LENGTH: f32 = 100.;
+pub const HANDLE_MAX_BANK_ANGLE: f32 = 60.;
+
+/// parameter of the selected track element changed by dragging a handle
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HandleKind {
+    /// end point, changes length or curve angle
+    Length,
+    /// arc midpoint, bends the element keeping its length
+    Radius,
+    /// left edge at the start, changes start bank angle
+    StartBank,
+    /// left edge at the end, changes end bank angle
+    EndBank,
+}
+
+impl HandleKind {
+    pub const ALL: [HandleKind; 4] = [
+        HandleKind::Length,
+        HandleKind::Radius,
+        HandleKind::StartBank,
+        HandleKind::EndBank,
+    ];
+}
+
+/// handle being dragged, the cursor ray is intersected with the drag plane
+#[derive(Clone, Copy, Debug)]
+pub struct HandleDrag {
+    pub kind: HandleKind,
+    pub element_index: usize,
+    pub plane_origin: Vec3,
+    pub plane_normal: Vec3,
+}
+
+#[derive(Resource, Default)]
+pub struct SelectionResource {
+    pub highlight_material: Handle<StandardMaterial>,
+    pub drag: Option<HandleDrag>,
+}
+
+/// draggable handle of the selected track element
+#[derive(Component)]
+pub struct ElementHandle {
+    pub kind: HandleKind,
+}
+
+// labels
+// copy of the selected track element mesh drawn with the highlight material
+#[derive(Component)]
+pub struct SelectionHighlight;
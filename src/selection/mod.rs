@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+pub mod components;
+pub mod systems;
+
+use components::*;
+use systems::*;
+
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectionResource>()
+            .add_startup_system(setup)
+            .add_system(drag_element_handles)
+            .add_system(pick_track_element.after(drag_element_handles))
+            .add_system(update_selection_highlight.after(pick_track_element))
+            .add_system(update_element_handles.after(drag_element_handles));
+    }
+}
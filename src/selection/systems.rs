@@ -0,0 +1,410 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_egui::EguiContexts;
+
+use crate::{
+    camera::components::{CameraAction, CameraInputMap, PanOrbitCamera},
+    components::*,
+    selection::components::*,
+    spline::components::SplineEditResource,
+    tools::raycast::raycast_mesh,
+    track::systems::{element_frame, element_length},
+    ui::components::UIResource,
+};
+
+pub fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut selection_resource: ResMut<SelectionResource>,
+) {
+    selection_resource.highlight_material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1.0, 0.55, 0.0, 0.6),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        double_sided: true,
+        cull_mode: None,
+        ..default()
+    });
+
+    // handles are hidden until an element is selected
+    let handle_mesh = meshes.add(Mesh::from(shape::UVSphere {
+        radius: HANDLE_RADIUS,
+        ..default()
+    }));
+
+    for kind in HandleKind::ALL {
+        let color = match kind {
+            HandleKind::Length => Color::rgb(0.2, 0.9, 0.2),
+            HandleKind::Radius => Color::rgb(0.2, 0.5, 1.0),
+            HandleKind::StartBank | HandleKind::EndBank => Color::rgb(0.9, 0.2, 0.9),
+        };
+
+        commands.spawn((
+            PbrBundle {
+                mesh: handle_mesh.clone(),
+                material: materials.add(StandardMaterial {
+                    base_color: color,
+                    unlit: true,
+                    ..default()
+                }),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ElementHandle { kind },
+        ));
+    }
+}
+
+/// world position of a handle of the element that starts at start
+pub fn handle_position(kind: HandleKind, track: &TrackElement2D, start: &Transform) -> Vec3 {
+    let left_edge = Vec3::new(0., track.width / 2., 0.);
+
+    match kind {
+        HandleKind::Length => (*start * element_frame(track, 1.)).translation,
+        HandleKind::Radius => (*start * element_frame(track, 0.5)).translation,
+        HandleKind::StartBank => (*start * element_frame(track, 0.)).transform_point(left_edge),
+        HandleKind::EndBank => (*start * element_frame(track, 1.)).transform_point(left_edge),
+    }
+}
+
+/// sets the element parameter of the handle from the dragged position
+pub fn apply_handle_drag(
+    track: &mut TrackElement2D,
+    kind: HandleKind,
+    start: &Transform,
+    position: Vec3,
+) {
+    // position in the element frame, x along the start tangent and y to the left
+    let local = start.compute_matrix().inverse().transform_point3(position);
+
+    match kind {
+        HandleKind::Length => {
+            if track.curvature == 0. {
+                track.length = local.x.clamp(0.5, HANDLE_MAX_LENGTH);
+            } else {
+                // angle swept around the center of the curve
+                let theta = (local.x * track.curvature.abs()).atan2(1. - local.y * track.curvature);
+                let mut curve_angle = theta.to_degrees();
+                if curve_angle < -90. {
+                    curve_angle += 360.;
+                }
+                track.curve_angle = curve_angle.clamp(1., HANDLE_MAX_CURVE_ANGLE);
+            }
+        }
+        HandleKind::Radius => {
+            // the arc from the start through the handle, keeping the element length
+            let length = element_length(track);
+            let distance_squared = local.truncate().length_squared();
+            if distance_squared < 1e-3 || length <= 0. {
+                return;
+            }
+
+            let curvature = (2. * local.y / distance_squared)
+                .clamp(-HANDLE_MAX_CURVATURE, HANDLE_MAX_CURVATURE);
+
+            if curvature.abs() < 1e-3 {
+                track.curvature = 0.;
+                track.length = length;
+            } else {
+                track.curvature = curvature;
+                track.curve_angle = (length * curvature.abs())
+                    .to_degrees()
+                    .clamp(1., HANDLE_MAX_CURVE_ANGLE);
+            }
+        }
+        HandleKind::StartBank | HandleKind::EndBank => {
+            let length_ratio = if kind == HandleKind::StartBank {
+                0.
+            } else {
+                1.
+            };
+            let frame = *start * element_frame(track, length_ratio);
+            let left = Vec3::Z.cross(frame.rotation * Vec3::X).normalize_or_zero();
+            let offset = position - frame.translation;
+
+            // positive bank raises the left edge
+            let bank_angle = offset
+                .z
+                .atan2(offset.dot(left))
+                .to_degrees()
+                .clamp(-HANDLE_MAX_BANK_ANGLE, HANDLE_MAX_BANK_ANGLE);
+
+            if kind == HandleKind::StartBank {
+                track.start_bank_angle = bank_angle;
+            } else {
+                track.end_bank_angle = bank_angle;
+            }
+        }
+    }
+}
+
+/// grabs a handle of the selected element on a left click and reshapes the element while it is
+/// dragged. Runs before picking so grabbing a handle doesn't change the selection.
+#[allow(clippy::too_many_arguments)]
+pub fn drag_element_handles(
+    input_mouse: Res<Input<MouseButton>>,
+    input_keys: Res<Input<KeyCode>>,
+    input_map: Res<CameraInputMap>,
+    mesh_resource: Res<MeshResource>,
+    global_resource: Res<GlobalResource>,
+    mut track_resource: ResMut<TrackResource>,
+    mut selection_resource: ResMut<SelectionResource>,
+    spline_edit_resource: Res<SplineEditResource>,
+    mut contexts: EguiContexts,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+) {
+    // clicks place control points while a spline section is edited
+    if global_resource.app_mode != AppMode::Editor
+        || !input_mouse.pressed(MouseButton::Left)
+        || spline_edit_resource.section.is_some()
+    {
+        if selection_resource.drag.is_some() {
+            selection_resource.drag = None;
+        }
+        return;
+    }
+
+    let (Ok(window), Ok((camera, camera_transform))) =
+        (window_query.get_single(), camera_query.get_single())
+    else {
+        return;
+    };
+    let Some(ray) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+    else {
+        return;
+    };
+    let direction = ray.direction.normalize();
+
+    if input_mouse.just_pressed(MouseButton::Left) {
+        if contexts.ctx_mut().is_pointer_over_area()
+            || input_map.pressed(CameraAction::Orbit, &input_keys, &input_mouse)
+            || input_map.pressed(CameraAction::Pan, &input_keys, &input_mouse)
+        {
+            return;
+        }
+
+        let Some((element_index, track, start)) =
+            selected_element(&global_resource, &track_resource, &mesh_resource)
+        else {
+            return;
+        };
+
+        // section elements are generated from their section
+        if track_resource.section_of(element_index).is_some() {
+            return;
+        }
+
+        // closest handle the ray passes through
+        let grabbed = HandleKind::ALL
+            .into_iter()
+            .filter_map(|kind| {
+                let position = handle_position(kind, track, &start);
+                let t = (position - ray.origin).dot(direction);
+                let miss = (ray.origin + direction * t).distance(position);
+                (t > 0. && miss < HANDLE_RADIUS).then_some((kind, position, t))
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2));
+
+        selection_resource.drag = grabbed.map(|(kind, position, _)| {
+            let (plane_origin, plane_normal) = match kind {
+                // length and bend are dragged in the ground plane
+                HandleKind::Length | HandleKind::Radius => (position, Vec3::Z),
+                // banking is dragged in the cross section
+                HandleKind::StartBank | HandleKind::EndBank => {
+                    let length_ratio = if kind == HandleKind::StartBank {
+                        0.
+                    } else {
+                        1.
+                    };
+                    let frame = start * element_frame(track, length_ratio);
+                    (frame.translation, frame.rotation * Vec3::X)
+                }
+            };
+
+            HandleDrag {
+                kind,
+                element_index,
+                plane_origin,
+                plane_normal,
+            }
+        });
+    }
+
+    let Some(drag) = selection_resource.drag else {
+        return;
+    };
+
+    // cursor ray against the drag plane
+    let denominator = direction.dot(drag.plane_normal);
+    if denominator.abs() < 1e-4 {
+        return;
+    }
+    let t = (drag.plane_origin - ray.origin).dot(drag.plane_normal) / denominator;
+    if t < 0. {
+        return;
+    }
+    let position = ray.origin + direction * t;
+
+    let Some(start) = mesh_resource
+        .track_mesh_transform_list
+        .get(drag.element_index)
+        .copied()
+    else {
+        return;
+    };
+    let Some(track) = track_resource.track_list.get_mut(drag.element_index) else {
+        return;
+    };
+
+    // update_track regenerates the element and moves the ones after it
+    apply_handle_drag(track, drag.kind, &start, position);
+}
+
+/// moves the handles to the selected element and hides them without a selection
+pub fn update_element_handles(
+    global_resource: Res<GlobalResource>,
+    track_resource: Res<TrackResource>,
+    mesh_resource: Res<MeshResource>,
+    mut handle_query: Query<(&ElementHandle, &mut Transform, &mut Visibility)>,
+) {
+    let selected = if global_resource.app_mode == AppMode::Editor {
+        selected_element(&global_resource, &track_resource, &mesh_resource)
+    } else {
+        None
+    };
+
+    for (handle, mut transform, mut visibility) in handle_query.iter_mut() {
+        match selected {
+            Some((_, track, start)) => {
+                transform.translation = handle_position(handle.kind, track, &start);
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+/// index, parameters and start transform of the highlighted element if it has been spawned
+fn selected_element<'a>(
+    global_resource: &GlobalResource,
+    track_resource: &'a TrackResource,
+    mesh_resource: &MeshResource,
+) -> Option<(usize, &'a TrackElement2D, Transform)> {
+    let index = usize::try_from(global_resource.highlighted_track_index).ok()?;
+    let track = track_resource.track_list.get(index)?;
+    let start = *mesh_resource.track_mesh_transform_list.get(index)?;
+    Some((index, track, start))
+}
+
+/// selects the track element under the cursor on a left click in the editor, unless the click
+/// grabbed a handle, edits a spline or picks a connection target
+#[allow(clippy::too_many_arguments)]
+pub fn pick_track_element(
+    input_mouse: Res<Input<MouseButton>>,
+    input_keys: Res<Input<KeyCode>>,
+    input_map: Res<CameraInputMap>,
+    meshes: Res<Assets<Mesh>>,
+    mesh_resource: Res<MeshResource>,
+    mut global_resource: ResMut<GlobalResource>,
+    mut ui_resource: ResMut<UIResource>,
+    selection_resource: Res<SelectionResource>,
+    spline_edit_resource: Res<SplineEditResource>,
+    mut contexts: EguiContexts,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+) {
+    if global_resource.app_mode != AppMode::Editor
+        || !input_mouse.just_pressed(MouseButton::Left)
+        || spline_edit_resource.section.is_some()
+        || selection_resource.drag.is_some()
+        || ui_resource.connection_pick.is_some()
+    {
+        return;
+    }
+
+    // clicks on the panels and left button camera bindings (e.g. alt + drag) aren't picks
+    if contexts.ctx_mut().is_pointer_over_area()
+        || input_map.pressed(CameraAction::Orbit, &input_keys, &input_mouse)
+        || input_map.pressed(CameraAction::Pan, &input_keys, &input_mouse)
+    {
+        return;
+    }
+
+    let (Ok(window), Ok((camera, camera_transform))) =
+        (window_query.get_single(), camera_query.get_single())
+    else {
+        return;
+    };
+    let Some(ray) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+    else {
+        return;
+    };
+
+    let mut closest: Option<(usize, f32)> = None;
+
+    for (index, (mesh_handle, transform)) in mesh_resource
+        .track_mesh_list
+        .iter()
+        .zip(mesh_resource.track_mesh_transform_list.iter())
+        .enumerate()
+    {
+        let Some(mesh) = meshes.get(mesh_handle) else {
+            continue;
+        };
+        if let Some(hit) = raycast_mesh(mesh, transform, ray.origin, ray.direction) {
+            if closest.is_none_or(|(_, distance)| hit.distance < distance) {
+                closest = Some((index, hit.distance));
+            }
+        }
+    }
+
+    if let Some((index, _)) = closest {
+        global_resource.highlighted_track_index = index as i32;
+        ui_resource.range_anchor = None;
+        ui_resource.scroll_to_selected = true;
+    }
+}
+
+/// respawns the highlight when the selection or the track meshes change
+pub fn update_selection_highlight(
+    mut commands: Commands,
+    mut prev_index: Local<Option<i32>>,
+    global_resource: Res<GlobalResource>,
+    mesh_resource: Res<MeshResource>,
+    selection_resource: Res<SelectionResource>,
+    highlight_query: Query<Entity, With<SelectionHighlight>>,
+) {
+    let index = global_resource.highlighted_track_index;
+    if *prev_index == Some(index) && !mesh_resource.is_changed() {
+        return;
+    }
+    *prev_index = Some(index);
+
+    for entity in highlight_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let Some((mesh_handle, transform)) = usize::try_from(index).ok().and_then(|index| {
+        Some((
+            mesh_resource.track_mesh_list.get(index)?,
+            mesh_resource.track_mesh_transform_list.get(index)?,
+        ))
+    }) else {
+        return;
+    };
+
+    commands.spawn((
+        PbrBundle {
+            mesh: mesh_handle.clone(),
+            material: selection_resource.highlight_material.clone(),
+            transform: Transform::from_translation(Vec3::Z * HIGHLIGHT_LIFT) * *transform,
+            ..default()
+        },
+        SelectionHighlight,
+    ));
+}
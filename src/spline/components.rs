@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+
+pub const CONTROL_POINT_RADIUS: f32 = 0.8; // ft
+pub const SPLINE_SAMPLE_LENGTH: f32 = 0.25; // ft, max spacing of the samples the spline is measured with
+pub const SPLINE_SEGMENT_LENGTH: f32 = 4.; // ft, max length of a fitted track element
+
+/// point a spline section passes through, unset values are interpolated between the points that
+/// set them and kept after the last one
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct ControlPoint {
+    /// world position in the ground plane
+    pub position: Vec2,
+    pub width: Option<f32>,
+    pub bank_angle: Option<f32>, // degrees
+    /// ft, the grades of the fitted elements climb to it
+    pub elevation: Option<f32>,
+}
+
+#[derive(Resource, Default)]
+pub struct SplineEditResource {
+    /// section whose control points are placed and dragged in the scene
+    pub section: Option<usize>,
+    /// control point being dragged
+    pub drag: Option<usize>,
+    pub marker_mesh: Handle<Mesh>,
+    pub marker_material: Handle<StandardMaterial>,
+}
+
+impl SplineEditResource {
+    /// keeps the edited section when a section is inserted at index
+    pub fn section_inserted(&mut self, index: usize) {
+        if let Some(section) = &mut self.section {
+            if *section >= index {
+                *section += 1;
+            }
+        }
+    }
+
+    /// keeps the edited section when the section at index is removed
+    pub fn section_removed(&mut self, index: usize) {
+        self.section = match self.section {
+            Some(section) if section == index => None,
+            Some(section) if section > index => Some(section - 1),
+            section => section,
+        };
+    }
+}
+
+// labels
+// control point of the edited section
+#[derive(Component)]
+pub struct ControlPointMarker;
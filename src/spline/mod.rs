@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+pub mod components;
+pub mod systems;
+
+use components::*;
+use systems::*;
+
+use crate::track::systems::update_track_sections;
+
+pub struct SplinePlugin;
+
+impl Plugin for SplinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SplineEditResource>()
+            .add_startup_system(setup)
+            .add_system(edit_control_points.before(update_track_sections))
+            .add_system(update_control_point_markers.after(update_track_sections));
+    }
+}
@@ -0,0 +1,358 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_egui::EguiContexts;
+
+use crate::{
+    camera::components::{CameraAction, CameraInputMap, PanOrbitCamera},
+    components::*,
+    spline::components::*,
+    track::systems::{element_length, TRACK_START_POSITION},
+};
+
+pub fn setup(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut spline_edit_resource: ResMut<SplineEditResource>,
+) {
+    spline_edit_resource.marker_mesh = meshes.add(Mesh::from(shape::UVSphere {
+        radius: CONTROL_POINT_RADIUS,
+        ..default()
+    }));
+    spline_edit_resource.marker_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.1, 0.9, 0.9),
+        unlit: true,
+        ..default()
+    });
+}
+
+/// track elements following a centripetal catmull-rom spline from start through the control
+/// points. Each element is the arc that leaves the end of the previous one and ends on the spline,
+/// so the control points are met exactly. Width and bank angle start from the given values, the
+/// grades climb from the height of start to the control point elevations.
+pub fn fit_spline_section(
+    control_points: &[ControlPoint],
+    start: &Transform,
+    start_width: f32,
+    start_bank_angle: f32,
+) -> Vec<TrackElement2D> {
+    let mut knots = vec![start.translation.truncate()];
+    let mut widths = vec![Some(start_width)];
+    let mut bank_angles = vec![Some(start_bank_angle)];
+    let mut elevations = vec![Some(start.translation.z)];
+
+    for control_point in control_points {
+        if knots
+            .last()
+            .is_some_and(|knot| knot.distance(control_point.position) < SPLINE_SAMPLE_LENGTH)
+        {
+            continue;
+        }
+        knots.push(control_point.position);
+        widths.push(control_point.width);
+        bank_angles.push(control_point.bank_angle);
+        elevations.push(control_point.elevation);
+    }
+
+    if knots.len() < 2 {
+        return vec![];
+    }
+
+    // extra end points, the one before the start keeps the heading of the previous element
+    let mut heading = (start.rotation * Vec3::X).truncate().normalize();
+    let first_span = knots[0].distance(knots[1]);
+    let last = knots[knots.len() - 1];
+    let before_last = knots[knots.len() - 2];
+    let mut extended = vec![knots[0] - heading * first_span];
+    extended.extend(&knots);
+    extended.push(last + (last - before_last));
+
+    // dense samples to measure arc length
+    let mut samples = vec![(0., knots[0])];
+    let mut knot_s = vec![0.];
+    for span in 0..knots.len() - 1 {
+        let points = [
+            extended[span],
+            extended[span + 1],
+            extended[span + 2],
+            extended[span + 3],
+        ];
+        let count = (knots[span].distance(knots[span + 1]) / SPLINE_SAMPLE_LENGTH)
+            .ceil()
+            .max(1.) as usize;
+
+        for sample in 1..=count {
+            let point = catmull_rom(points, sample as f32 / count as f32);
+            let (s, prev_point) = samples[samples.len() - 1];
+            samples.push((s + prev_point.distance(point), point));
+        }
+        knot_s.push(samples[samples.len() - 1].0);
+    }
+
+    // element ends, evenly spaced between neighbouring knots
+    let mut ends = vec![];
+    for span in 0..knots.len() - 1 {
+        let length = knot_s[span + 1] - knot_s[span];
+        let count = (length / SPLINE_SEGMENT_LENGTH).ceil().max(1.) as usize;
+        for end in 1..=count {
+            ends.push(knot_s[span] + length * end as f32 / count as f32);
+        }
+    }
+
+    let mut track_list = vec![];
+    let mut position = knots[0];
+    let mut s = 0.;
+
+    for end_s in ends {
+        let chord = sample_at(&samples, end_s) - position;
+        let chord_length = chord.length();
+        if chord_length < f32::EPSILON {
+            continue;
+        }
+
+        let width = knot_value(&knot_s, &widths, (s + end_s) / 2.);
+        let start_bank_angle = knot_value(&knot_s, &bank_angles, s);
+        let end_bank_angle = knot_value(&knot_s, &bank_angles, end_s);
+        let rise = knot_value(&knot_s, &elevations, end_s) - knot_value(&knot_s, &elevations, s);
+
+        // the arc through both ends turns by twice the angle between heading and chord
+        let angle = heading.angle_between(chord);
+        let mut track = if angle.abs() < 1e-4 {
+            TrackElement2D {
+                curvature: 0.,
+                curve_angle: 0.,
+                start_bank_angle,
+                end_bank_angle,
+                width,
+                length: chord_length,
+                grade: 0.,
+                bank_pivot: BankPivot::InsideEdge,
+            }
+        } else {
+            heading = Vec2::from_angle(2. * angle).rotate(heading);
+            TrackElement2D {
+                curvature: 2. * angle.sin() / chord_length,
+                curve_angle: (2. * angle.abs()).to_degrees(),
+                start_bank_angle,
+                end_bank_angle,
+                width,
+                length: 0.,
+                grade: 0.,
+                bank_pivot: BankPivot::InsideEdge,
+            }
+        };
+        track.grade = rise / element_length(&track) * 100.;
+        track_list.push(track);
+
+        position += chord;
+        s = end_s;
+    }
+
+    track_list
+}
+
+/// centripetal catmull-rom between points[1] and points[2], t from 0 to 1
+fn catmull_rom(points: [Vec2; 4], t: f32) -> Vec2 {
+    let knot = |a: Vec2, b: Vec2| a.distance(b).sqrt().max(1e-4);
+    let t0 = 0.;
+    let t1 = t0 + knot(points[0], points[1]);
+    let t2 = t1 + knot(points[1], points[2]);
+    let t3 = t2 + knot(points[2], points[3]);
+    let t = t1 + (t2 - t1) * t;
+
+    let a1 = (points[0] * (t1 - t) + points[1] * (t - t0)) / (t1 - t0);
+    let a2 = (points[1] * (t2 - t) + points[2] * (t - t1)) / (t2 - t1);
+    let a3 = (points[2] * (t3 - t) + points[3] * (t - t2)) / (t3 - t2);
+    let b1 = (a1 * (t2 - t) + a2 * (t - t0)) / (t2 - t0);
+    let b2 = (a2 * (t3 - t) + a3 * (t - t1)) / (t3 - t1);
+
+    (b1 * (t2 - t) + b2 * (t - t1)) / (t2 - t1)
+}
+
+/// point on the dense samples at arc length s
+fn sample_at(samples: &[(f32, Vec2)], s: f32) -> Vec2 {
+    let next = samples
+        .partition_point(|(sample_s, _)| *sample_s < s)
+        .clamp(1, samples.len() - 1);
+    let (s_a, a) = samples[next - 1];
+    let (s_b, b) = samples[next];
+    let fraction = if s_b > s_a {
+        (s - s_a) / (s_b - s_a)
+    } else {
+        1.
+    };
+    a.lerp(b, fraction.clamp(0., 1.))
+}
+
+/// value at arc length s interpolated between the knots that set it, the first knot always does
+fn knot_value(knot_s: &[f32], values: &[Option<f32>], s: f32) -> f32 {
+    let mut before = (knot_s[0], values[0].unwrap_or_default());
+
+    for (knot, value) in knot_s.iter().zip(values) {
+        let Some(value) = value else {
+            continue;
+        };
+        if *knot <= s {
+            before = (*knot, *value);
+        } else {
+            let fraction = (s - before.0) / (knot - before.0);
+            return before.1 + (value - before.1) * fraction;
+        }
+    }
+
+    before.1
+}
+
+/// where a ray meets the ground plane of the track, none when it points away from it
+pub fn ground_position(ray: &Ray) -> Option<Vec2> {
+    let direction = ray.direction.normalize();
+    if direction.z.abs() < 1e-4 {
+        return None;
+    }
+
+    let t = (TRACK_START_POSITION.z - ray.origin.z) / direction.z;
+    (t >= 0.).then(|| (ray.origin + direction * t).truncate())
+}
+
+/// in the edited section a left click on the ground adds a control point and dragging a control
+/// point moves it
+#[allow(clippy::too_many_arguments)]
+pub fn edit_control_points(
+    input_mouse: Res<Input<MouseButton>>,
+    input_keys: Res<Input<KeyCode>>,
+    input_map: Res<CameraInputMap>,
+    global_resource: Res<GlobalResource>,
+    mut track_resource: ResMut<TrackResource>,
+    mut spline_edit_resource: ResMut<SplineEditResource>,
+    mut contexts: EguiContexts,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+) {
+    let editing = spline_edit_resource.section.filter(|section| {
+        track_resource
+            .sections
+            .get(*section)
+            .is_some_and(|section| section.control_points().is_some())
+    });
+    let Some(section_index) = editing.filter(|_| global_resource.app_mode == AppMode::Editor)
+    else {
+        if spline_edit_resource.section.is_some() && editing.is_none() {
+            spline_edit_resource.section = None;
+        }
+        return;
+    };
+
+    if !input_mouse.pressed(MouseButton::Left) {
+        if spline_edit_resource.drag.is_some() {
+            spline_edit_resource.drag = None;
+        }
+        return;
+    }
+
+    let (Ok(window), Ok((camera, camera_transform))) =
+        (window_query.get_single(), camera_query.get_single())
+    else {
+        return;
+    };
+    let Some(ray) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+    else {
+        return;
+    };
+    let direction = ray.direction.normalize();
+
+    // control points sit in the ground plane of the track
+    let ground = TRACK_START_POSITION.z;
+    let Some(position) = ground_position(&ray) else {
+        return;
+    };
+
+    if input_mouse.just_pressed(MouseButton::Left) {
+        if contexts.ctx_mut().is_pointer_over_area()
+            || input_map.pressed(CameraAction::Orbit, &input_keys, &input_mouse)
+            || input_map.pressed(CameraAction::Pan, &input_keys, &input_mouse)
+        {
+            return;
+        }
+
+        let Some(control_points) = track_resource.sections[section_index].control_points() else {
+            return;
+        };
+        let grabbed = control_points
+            .iter()
+            .enumerate()
+            .filter_map(|(index, control_point)| {
+                let point = control_point.position.extend(ground);
+                let t = (point - ray.origin).dot(direction);
+                let miss = (ray.origin + direction * t).distance(point);
+                (t > 0. && miss < CONTROL_POINT_RADIUS).then_some((index, t))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index);
+
+        // a click on the ground adds a point that can be dragged right away
+        spline_edit_resource.drag = grabbed.or_else(|| {
+            let control_points = track_resource.sections[section_index].control_points_mut()?;
+            control_points.push(ControlPoint {
+                position,
+                ..default()
+            });
+            Some(control_points.len() - 1)
+        });
+        return;
+    }
+
+    let Some(drag) = spline_edit_resource.drag else {
+        return;
+    };
+    let moved = track_resource.sections[section_index]
+        .control_points()
+        .and_then(|control_points| control_points.get(drag))
+        .is_some_and(|control_point| control_point.position != position);
+
+    // update_track_sections refits the elements
+    if moved {
+        if let Some(control_points) = track_resource.sections[section_index].control_points_mut() {
+            control_points[drag].position = position;
+        }
+    }
+}
+
+/// respawns the control point markers when the control points of the edited section change
+pub fn update_control_point_markers(
+    mut commands: Commands,
+    mut prev_control_points: Local<Option<Vec<ControlPoint>>>,
+    track_resource: Res<TrackResource>,
+    spline_edit_resource: Res<SplineEditResource>,
+    marker_query: Query<Entity, With<ControlPointMarker>>,
+) {
+    let control_points = spline_edit_resource
+        .section
+        .and_then(|section| track_resource.sections.get(section))
+        .and_then(|section| section.control_points().cloned());
+    if *prev_control_points == control_points {
+        return;
+    }
+
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    *prev_control_points = control_points;
+    let Some(control_points) = prev_control_points.as_ref() else {
+        return;
+    };
+
+    for control_point in control_points {
+        commands.spawn((
+            PbrBundle {
+                mesh: spline_edit_resource.marker_mesh.clone(),
+                material: spline_edit_resource.marker_material.clone(),
+                transform: Transform::from_translation(
+                    control_point.position.extend(TRACK_START_POSITION.z),
+                ),
+                ..default()
+            },
+            ControlPointMarker,
+        ));
+    }
+}
@@ -9,8 +9,8 @@ use bevy::{
 use crate::components::*;
 
 pub const TERRAIN_OFFSET: f32 = 25.; // how much the terrain extends past track
-pub const MAX_TRACK_HEIGHT: f32 = 5.; // positions secondary camera for track height map
-pub const MIN_TRACK_HEIGHT: f32 = -2.; // gives a bottom depth for track height map
+pub const MAX_TRACK_HEIGHT: f32 = 40.; // positions secondary camera for track height map
+pub const MIN_TRACK_HEIGHT: f32 = -20.; // gives a bottom depth for track height map
 
 // sets initial values
 pub fn set_global_resource(mut global_resource: ResMut<GlobalResource>) {
@@ -102,12 +102,6 @@ pub fn set_visibility(
     }
 }
 
-pub fn despawn(commands: &mut Commands, query: &mut Query<Entity, With<MyEntity>>) {
-    for track in query {
-        commands.entity(track).despawn();
-    }
-}
-
 pub fn directional_light(mut commands: Commands) {
     commands.spawn(DirectionalLightBundle {
         directional_light: DirectionalLight {
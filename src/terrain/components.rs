@@ -5,10 +5,20 @@ use bevy::{
         extract_component::ExtractComponent,
         render_resource::{AsBindGroup, ShaderRef, ShaderType},
     },
+    tasks::Task,
 };
 
 use crate::GlobalResource;
 
+pub const NOISE_SEED: f32 = 1.;
+
+/// terrain mesh being generated on the async compute pool
+#[derive(Resource, Default)]
+pub struct TerrainGenerationResource {
+    /// replacing the task cancels the previous build
+    pub task: Option<Task<Mesh>>,
+}
+
 // noise parameters
 #[derive(Component, Clone, ExtractComponent, ShaderType)]
 pub struct NoiseParams {
@@ -35,7 +45,7 @@ impl NoiseParams {
     // constructor
     pub fn new(global_resource: &ResMut<GlobalResource>) -> NoiseParams {
         NoiseParams {
-            noise_seed: 1.,
+            noise_seed: NOISE_SEED,
             frequency_scale: global_resource.frequency_scale,
             amplitude_scale: global_resource.amplitude_scale,
             octaves: global_resource.octaves as u32,
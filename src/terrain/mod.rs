@@ -7,13 +7,17 @@ use components::*;
 use noisy_bevy::NoisyShaderPlugin;
 use systems::*;
 
+use crate::track::systems::update_track;
+
 pub struct TerrainPlugin;
 
 impl Plugin for TerrainPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(MaterialPlugin::<TerrainMaterial>::default())
             .add_plugin(NoisyShaderPlugin)
+            .init_resource::<TerrainGenerationResource>()
             .add_startup_system(setup)
-            .add_system(update_noise_params);
+            .add_system(update_noise_params)
+            .add_system(update_terrain_mesh.after(update_track));
     }
 }
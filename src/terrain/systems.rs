@@ -1,10 +1,13 @@
 use bevy::{
     prelude::*,
-    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    render::{mesh::Indices, primitives::Aabb, render_resource::PrimitiveTopology},
+    tasks::AsyncComputeTaskPool,
 };
+use futures_lite::future;
 
 use crate::components::*;
 use crate::terrain::components::*;
+use noisy_bevy::fbm_simplex_2d_seeded;
 
 pub const Y_SUB_MAX_LEN: f32 = 0.3;
 pub const X_SUB_MAX_LEN: f32 = 0.3;
@@ -59,7 +62,54 @@ pub fn spawn_terrain(
     ));
 }
 
-pub fn terrain_mesh(global_resource: &ResMut<GlobalResource>, mesh: &mut Mesh) -> bool {
+/// rebuilds the terrain mesh in place when the track extent changed
+pub fn update_terrain_mesh(
+    mut commands: Commands,
+    mut prev_extent: Local<Option<Vec4>>,
+    global_resource: Res<GlobalResource>,
+    mesh_resource: Res<MeshResource>,
+    mut generation_resource: ResMut<TerrainGenerationResource>,
+    mut mesh_assets: ResMut<Assets<Mesh>>,
+    terrain_query: Query<(Entity, &Handle<Mesh>), With<TerrainElement>>,
+) {
+    if mesh_resource.is_changed() {
+        let extent = Vec4::new(
+            global_resource.x_min,
+            global_resource.x_max,
+            global_resource.y_min,
+            global_resource.y_max,
+        );
+
+        // a new extent replaces (and cancels) a build that is still running
+        if *prev_extent != Some(extent) {
+            *prev_extent = Some(extent);
+
+            let global_resource = global_resource.clone();
+            generation_resource.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+                let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+                terrain_mesh(&global_resource, &mut mesh);
+                mesh
+            }));
+        }
+    }
+
+    let Some(task) = generation_resource.task.as_mut() else {
+        return;
+    };
+    let Some(mesh) = future::block_on(future::poll_once(task)) else {
+        return;
+    };
+    generation_resource.task = None;
+
+    for (entity, mesh_handle) in terrain_query.iter() {
+        if let Some(terrain_mesh) = mesh_assets.get_mut(mesh_handle) {
+            *terrain_mesh = mesh.clone();
+        }
+        commands.entity(entity).remove::<Aabb>();
+    }
+}
+
+pub fn terrain_mesh(global_resource: &GlobalResource, mesh: &mut Mesh) -> bool {
     // vectors that define mesh
     let mut indices = vec![];
     let mut positions = vec![];
@@ -122,6 +172,17 @@ pub fn terrain_mesh(global_resource: &ResMut<GlobalResource>, mesh: &mut Mesh) -
     true //return
 }
 
+/// cpu side copy of z_height in terrain_shader.wgsl, noise height before blending with track
+pub fn terrain_noise_height(global_resource: &GlobalResource, p: Vec2) -> f32 {
+    fbm_simplex_2d_seeded(
+        p * global_resource.frequency_scale,
+        global_resource.octaves,
+        global_resource.lacunarity,
+        global_resource.gain,
+        NOISE_SEED,
+    ) * global_resource.amplitude_scale
+}
+
 // update noise parameters of Terrain Material to global state
 pub fn update_noise_params(
     global_resource: ResMut<GlobalResource>,
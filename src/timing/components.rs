@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+
+pub const DEFAULT_CHECKPOINT_SPACING: f32 = 20.; // ft
+pub const MAX_PROGRESS_STEP: f32 = 5.; // larger forward jumps per tick are shortcuts (ft)
+pub const OFF_TRACK_MARGIN: f32 = 2.; // distance past the track edge still counted on track
+pub const STANDING_START_TOLERANCE: f32 = 3.; // a first lap started within this of s = 0 counts
+pub const FINISH_TOLERANCE: f32 = 0.5; // distance before the end of an open track that finishes
+
+/// how a sector boundary is placed on the track
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SectorBoundaryKind {
+    #[default]
+    ElementIndex,
+    ArcLength,
+}
+
+/// user defined sector boundary, either the start of a track element or an arc length
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct SectorBoundary {
+    pub kind: SectorBoundaryKind,
+    pub element_index: usize,
+    pub s: f32,
+}
+
+/// gate that must be passed on track and in order for a lap to count
+#[derive(Clone, Copy, Debug)]
+pub struct Checkpoint {
+    pub s: f32,
+    /// index of the sector that ends at this checkpoint
+    pub sector_end: Option<usize>,
+}
+
+#[derive(Resource)]
+pub struct TimingResource {
+    // user parameters
+    pub sector_boundaries: Vec<SectorBoundary>,
+    pub checkpoint_spacing: f32,
+
+    // derived from the parameters and the centerline, the start/finish line is at s = 0
+    pub sector_start_list: Vec<f32>,
+    pub checkpoint_list: Vec<Checkpoint>,
+}
+
+impl Default for TimingResource {
+    fn default() -> Self {
+        TimingResource {
+            sector_boundaries: vec![],
+            checkpoint_spacing: DEFAULT_CHECKPOINT_SPACING,
+            sector_start_list: vec![0.],
+            checkpoint_list: vec![],
+        }
+    }
+}
+
+impl TimingResource {
+    pub fn sector_count(&self) -> usize {
+        self.sector_start_list.len()
+    }
+}
+
+/// lap and sector times of a vehicle, advanced on the fixed timestep
+#[derive(Component, Default)]
+pub struct LapTimer {
+    pub lap_time: f32,
+    pub lap_count: u32,
+    /// false once a checkpoint is missed or the track is cut
+    pub valid: bool,
+    pub sector_times: Vec<f32>,
+
+    pub last_lap: Option<f32>,
+    pub last_lap_valid: bool,
+    pub last_sector_times: Vec<f32>,
+
+    pub best_lap: Option<f32>,
+    pub best_sector_times: Vec<Option<f32>>,
+
+    // progress
+    pub started: bool,
+    pub finished: bool,
+    pub next_checkpoint: usize,
+    pub progress_index: usize,
+    pub prev_s: f32,
+    pub sector_start_time: f32,
+}
+
+impl LapTimer {
+    /// starts timing a lap from arc length s, skipping checkpoints behind the vehicle
+    pub fn start_lap(&mut self, s: f32, timing_resource: &TimingResource) {
+        self.lap_time = 0.;
+        self.sector_times.clear();
+        self.sector_start_time = 0.;
+        self.started = true;
+        self.finished = false;
+        self.valid = s <= STANDING_START_TOLERANCE;
+        self.next_checkpoint = timing_resource
+            .checkpoint_list
+            .partition_point(|checkpoint| checkpoint.s <= s);
+        self.prev_s = s;
+        self.best_sector_times
+            .resize(timing_resource.sector_count(), None);
+    }
+
+    /// ends the sector that is being driven
+    pub fn split_sector(&mut self) {
+        self.sector_times
+            .push(self.lap_time - self.sector_start_time);
+        self.sector_start_time = self.lap_time;
+    }
+
+    /// records the lap that just crossed the finish line
+    pub fn complete_lap(&mut self, timing_resource: &TimingResource) {
+        self.split_sector();
+
+        let valid = self.valid && self.next_checkpoint == timing_resource.checkpoint_list.len();
+
+        self.lap_count += 1;
+        self.last_lap = Some(self.lap_time);
+        self.last_lap_valid = valid;
+        self.last_sector_times = self.sector_times.clone();
+
+        if valid {
+            if self.best_lap.is_none_or(|best| self.lap_time < best) {
+                self.best_lap = Some(self.lap_time);
+            }
+
+            self.best_sector_times
+                .resize(timing_resource.sector_count(), None);
+            for (best, time) in self
+                .best_sector_times
+                .iter_mut()
+                .zip(self.sector_times.iter())
+            {
+                if best.is_none_or(|best| *time < best) {
+                    *best = Some(*time);
+                }
+            }
+        }
+    }
+
+    /// clears all times, e.g. after the track or the sectors changed
+    pub fn reset(&mut self) {
+        *self = LapTimer::default();
+    }
+}
+
+/// formats seconds as m:ss.sss
+pub fn format_lap_time(time: f32) -> String {
+    let minutes = (time / 60.).floor();
+    format!("{}:{:06.3}", minutes as u32, time - minutes * 60.)
+}
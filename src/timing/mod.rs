@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+pub mod components;
+pub mod systems;
+
+use components::*;
+use systems::*;
+
+use crate::vehicle::systems::vehicle_physics;
+
+pub struct TimingPlugin;
+
+impl Plugin for TimingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimingResource>()
+            .add_system(update_checkpoints)
+            .add_system(add_lap_timers)
+            .add_system(
+                update_lap_timers
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .after(vehicle_physics),
+            );
+    }
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use crate::{timing::components::*, track::components::*, vehicle::components::*};
+
+/// places sector starts and checkpoints on the centerline when the track or the sector
+/// boundaries change, existing lap times no longer compare so they are cleared
+pub fn update_checkpoints(
+    centerline_resource: Res<CenterlineResource>,
+    mut timing_resource: ResMut<TimingResource>,
+    mut timer_query: Query<&mut LapTimer>,
+) {
+    if !centerline_resource.is_changed() && !timing_resource.is_changed() {
+        return;
+    }
+
+    // derived values are written without flagging a change to avoid rebuilding every frame
+    let timing_resource = timing_resource.bypass_change_detection();
+    let length = centerline_resource.length;
+
+    // sector starts, the first sector starts at the start/finish line
+    let mut sector_start_list = vec![0.];
+    for boundary in timing_resource.sector_boundaries.iter() {
+        let s = match boundary.kind {
+            SectorBoundaryKind::ElementIndex => centerline_resource
+                .points
+                .iter()
+                .find(|point| point.element_index == boundary.element_index)
+                .map(|point| point.s),
+            SectorBoundaryKind::ArcLength => Some(boundary.s),
+        };
+
+        if let Some(s) = s {
+            if s > 0. && s < length {
+                sector_start_list.push(s);
+            }
+        }
+    }
+    sector_start_list.sort_by(|a, b| a.total_cmp(b));
+    sector_start_list.dedup_by(|a, b| (*a - *b).abs() < 0.01);
+
+    // checkpoints at each sector boundary and evenly spaced in between
+    let mut checkpoint_list: Vec<Checkpoint> = sector_start_list
+        .iter()
+        .skip(1)
+        .enumerate()
+        .map(|(sector, s)| Checkpoint {
+            s: *s,
+            sector_end: Some(sector),
+        })
+        .collect();
+
+    let spacing = timing_resource.checkpoint_spacing.max(1.);
+    let mut s = spacing;
+    while s < length - spacing / 2. {
+        checkpoint_list.push(Checkpoint {
+            s,
+            sector_end: None,
+        });
+        s += spacing;
+    }
+    checkpoint_list.sort_by(|a, b| a.s.total_cmp(&b.s));
+
+    timing_resource.sector_start_list = sector_start_list;
+    timing_resource.checkpoint_list = checkpoint_list;
+
+    for mut timer in timer_query.iter_mut() {
+        timer.reset();
+    }
+}
+
+/// every vehicle gets timed
+pub fn add_lap_timers(
+    mut commands: Commands,
+    vehicle_query: Query<Entity, (With<Vehicle>, Without<LapTimer>)>,
+) {
+    for entity in vehicle_query.iter() {
+        commands.entity(entity).insert(LapTimer::default());
+    }
+}
+
+/// advances lap timers on the fixed timestep and validates checkpoints
+pub fn update_lap_timers(
+    fixed_time: Res<FixedTime>,
+    centerline_resource: Res<CenterlineResource>,
+    timing_resource: Res<TimingResource>,
+    mut timer_query: Query<(&Transform, &mut LapTimer)>,
+) {
+    let dt = fixed_time.period.as_secs_f32();
+    let points = &centerline_resource.points;
+    let length = centerline_resource.length;
+
+    if points.len() < 2 {
+        return;
+    }
+
+    for (transform, mut timer) in timer_query.iter_mut() {
+        let position = transform.translation;
+
+        // progress along the track
+        let mut index = centerline_resource
+            .closest_index_near(position, timer.progress_index, TRACKING_WINDOW)
+            .unwrap_or(0);
+        if points[index]
+            .position
+            .truncate()
+            .distance(position.truncate())
+            > LOST_DISTANCE
+        {
+            index = centerline_resource.closest_index(position).unwrap_or(0);
+        }
+        timer.progress_index = index;
+
+        let point = points[index];
+        let s = point.s;
+        let on_track = point.position.truncate().distance(position.truncate())
+            <= point.width / 2. + OFF_TRACK_MARGIN;
+
+        if !timer.started {
+            timer.start_lap(s, &timing_resource);
+            continue;
+        }
+
+        // signed progress this tick, across the start/finish line on loops
+        let mut ds = s - timer.prev_s;
+        if centerline_resource.closed {
+            if ds > length / 2. {
+                ds -= length;
+            } else if ds < -length / 2. {
+                ds += length;
+            }
+        }
+
+        // moved back a long way, e.g. reset to the start
+        if ds < -MAX_PROGRESS_STEP {
+            timer.start_lap(s, &timing_resource);
+            continue;
+        }
+
+        if timer.finished {
+            timer.prev_s = s;
+            continue;
+        }
+
+        timer.lap_time += dt;
+
+        // skipped part of the track
+        if ds > MAX_PROGRESS_STEP {
+            timer.valid = false;
+        }
+
+        let prev_s = timer.prev_s;
+        let crossed_line = centerline_resource.closed && ds > 0. && s < prev_s;
+
+        if crossed_line {
+            pass_checkpoints(&mut timer, &timing_resource, length, on_track);
+            timer.complete_lap(&timing_resource);
+            timer.start_lap(0., &timing_resource);
+            pass_checkpoints(&mut timer, &timing_resource, s, on_track);
+        } else if ds > 0. {
+            pass_checkpoints(&mut timer, &timing_resource, s, on_track);
+
+            // open tracks finish at the end instead of looping
+            if !centerline_resource.closed && s >= length - FINISH_TOLERANCE {
+                timer.complete_lap(&timing_resource);
+                timer.finished = true;
+            }
+        }
+
+        timer.prev_s = s;
+    }
+}
+
+/// passes all checkpoints up to arc length s, checkpoints passed off track invalidate the lap
+fn pass_checkpoints(
+    timer: &mut LapTimer,
+    timing_resource: &TimingResource,
+    s: f32,
+    on_track: bool,
+) {
+    while let Some(checkpoint) = timing_resource.checkpoint_list.get(timer.next_checkpoint) {
+        if checkpoint.s > s {
+            break;
+        }
+
+        if !on_track {
+            timer.valid = false;
+        }
+
+        if checkpoint.sector_end.is_some() {
+            timer.split_sector();
+        }
+
+        timer.next_checkpoint += 1;
+    }
+}
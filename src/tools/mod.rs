@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 
+pub mod raycast;
 mod systems;
 
 use systems::*;
@@ -0,0 +1,171 @@
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, VertexAttributeValues},
+};
+
+// triangles closer to parallel than this are ignored
+pub const RAY_EPSILON: f32 = 1e-6;
+
+/// closest intersection of a ray with a mesh
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    /// distance along the (normalized) ray direction in world space
+    pub distance: f32,
+    pub position: Vec3,
+    pub normal: Vec3,
+    /// vertex indices of the hit triangle
+    pub triangle: [usize; 3],
+    /// barycentric weights of the hit point for the three triangle vertices
+    pub barycentric: Vec3,
+}
+
+/// Möller–Trumbore ray/triangle intersection, returns distance along direction and
+/// barycentric weights
+pub fn ray_triangle_intersection(
+    origin: Vec3,
+    direction: Vec3,
+    v0: Vec3,
+    v1: Vec3,
+    v2: Vec3,
+) -> Option<(f32, Vec3)> {
+    let edge_1 = v1 - v0;
+    let edge_2 = v2 - v0;
+
+    let p = direction.cross(edge_2);
+    let det = edge_1.dot(p);
+
+    // ray is parallel to triangle (both faces are hit)
+    if det.abs() < RAY_EPSILON {
+        return None;
+    }
+
+    let inv_det = 1. / det;
+    let s = origin - v0;
+    let u = s.dot(p) * inv_det;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(edge_1);
+    let v = direction.dot(q) * inv_det;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+
+    let t = edge_2.dot(q) * inv_det;
+    if t < 0. {
+        return None;
+    }
+
+    Some((t, Vec3::new(1. - u - v, u, v)))
+}
+
+/// casts a world space ray against a triangle list mesh placed with transform and returns the
+/// closest hit
+pub fn raycast_mesh(
+    mesh: &Mesh,
+    transform: &Transform,
+    origin: Vec3,
+    direction: Vec3,
+) -> Option<RayHit> {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return None;
+    };
+
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(Indices::U32(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        Some(Indices::U16(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        None => (0..positions.len()).collect(),
+    };
+
+    // move ray to mesh space so the vertices don't have to be transformed
+    let matrix = transform.compute_matrix();
+    let inverse = matrix.inverse();
+    let local_origin = inverse.transform_point3(origin);
+    let local_direction = inverse.transform_vector3(direction.normalize());
+
+    let mut closest: Option<(f32, [usize; 3], Vec3)> = None;
+
+    for triangle in indices.chunks_exact(3) {
+        let v0 = Vec3::from(positions[triangle[0]]);
+        let v1 = Vec3::from(positions[triangle[1]]);
+        let v2 = Vec3::from(positions[triangle[2]]);
+
+        if let Some((t, barycentric)) =
+            ray_triangle_intersection(local_origin, local_direction, v0, v1, v2)
+        {
+            if closest.is_none_or(|(closest_t, _, _)| t < closest_t) {
+                closest = Some((t, [triangle[0], triangle[1], triangle[2]], barycentric));
+            }
+        }
+    }
+
+    let (t, triangle, barycentric) = closest?;
+
+    let local_position = local_origin + local_direction * t;
+    let position = matrix.transform_point3(local_position);
+
+    let v0 = Vec3::from(positions[triangle[0]]);
+    let v1 = Vec3::from(positions[triangle[1]]);
+    let v2 = Vec3::from(positions[triangle[2]]);
+    let mut normal = matrix
+        .transform_vector3((v1 - v0).cross(v2 - v0))
+        .normalize_or_zero();
+
+    // always report the face pointing back at the ray
+    if normal.dot(direction) > 0. {
+        normal = -normal;
+    }
+
+    Some(RayHit {
+        distance: (position - origin).length(),
+        position,
+        normal,
+        triangle,
+        barycentric,
+    })
+}
+
+/// interpolates a Float32x4 vertex attribute (e.g. vertex colors) at a ray hit
+pub fn interpolate_vec4_attribute(
+    mesh: &Mesh,
+    attribute: impl Into<bevy::render::mesh::MeshVertexAttributeId>,
+    hit: &RayHit,
+) -> Option<Vec4> {
+    let Some(VertexAttributeValues::Float32x4(values)) = mesh.attribute(attribute) else {
+        return None;
+    };
+
+    Some(
+        Vec4::from(values[hit.triangle[0]]) * hit.barycentric.x
+            + Vec4::from(values[hit.triangle[1]]) * hit.barycentric.y
+            + Vec4::from(values[hit.triangle[2]]) * hit.barycentric.z,
+    )
+}
+
+/// world space axis aligned bounds of a mesh placed with transform
+pub fn mesh_world_bounds(mesh: &Mesh, transform: &Transform) -> Option<(Vec3, Vec3)> {
+    let aabb = mesh.compute_aabb()?;
+    let matrix = transform.compute_matrix();
+
+    let center = Vec3::from(aabb.center);
+    let half_extents = Vec3::from(aabb.half_extents);
+
+    let mut min = Vec3::splat(f32::MAX);
+    let mut max = Vec3::splat(f32::MIN);
+
+    for corner in 0..8 {
+        let sign = Vec3::new(
+            if corner & 1 == 0 { -1. } else { 1. },
+            if corner & 2 == 0 { -1. } else { 1. },
+            if corner & 4 == 0 { -1. } else { 1. },
+        );
+        let p = matrix.transform_point3(center + sign * half_extents);
+        min = min.min(p);
+        max = max.max(p);
+    }
+
+    Some((min, max))
+}
@@ -0,0 +1,290 @@
+use std::ops::Range;
+
+use crate::{
+    components::TrackElement2D, track::systems::element_length, vehicle::components::GRAVITY,
+};
+
+const MIN_PIECE_LENGTH: f32 = 0.01; // ft, elements aren't split closer than this to their ends
+
+/// superelevation of curves for a design speed, lengths in ft and angles in degrees
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BankingParams {
+    pub design_speed: f32, // ft/s
+    /// side friction the tires contribute, the bank covers the rest of the lateral acceleration
+    pub friction: f32,
+    pub max_bank_angle: f32,
+    /// length the bank ramps in and out over, on the straight next to a curve when there is one
+    pub runout_length: f32,
+}
+
+impl Default for BankingParams {
+    fn default() -> Self {
+        BankingParams {
+            design_speed: 60.,
+            friction: 0.1,
+            max_bank_angle: 30.,
+            runout_length: 20.,
+        }
+    }
+}
+
+/// required bank angle of an element, tan(bank) = (v^2 / g r - f) / (1 + f v^2 / g r). Left turns
+/// bank negative so the outside edge is raised, straights are flat.
+pub fn required_bank_angle(params: &BankingParams, curvature: f32) -> f32 {
+    if curvature == 0. {
+        return 0.;
+    }
+
+    let lateral = params.design_speed.powi(2) * curvature.abs() / GRAVITY;
+    let bank = ((lateral - params.friction) / (1. + params.friction * lateral))
+        .atan()
+        .to_degrees()
+        .clamp(0., params.max_bank_angle);
+
+    -curvature.signum() * bank
+}
+
+/// the track list with every curve banked at its required angle, each piece with the index of
+/// the element it was split from. The bank changes over runouts at the ends of the straights next
+/// to the curves, or at the ends of a curve next to another curve or a fixed element. Fixed
+/// elements keep their banking and are never split. Elements are only split where a runout starts
+/// or ends inside them, so banking a banked track list gives the same list.
+pub fn apply_banking(
+    params: &BankingParams,
+    track_list: &[TrackElement2D],
+    fixed: &[bool],
+    closed: bool,
+) -> Vec<(usize, TrackElement2D)> {
+    // runs of plain elements with the same curvature are banked as one, fixed elements are runs
+    // of their own
+    let mut runs: Vec<Range<usize>> = vec![];
+    for (index, track) in track_list.iter().enumerate() {
+        match runs.last_mut() {
+            Some(run)
+                if !fixed[index]
+                    && !fixed[run.start]
+                    && track_list[run.start].curvature == track.curvature =>
+            {
+                run.end = index + 1
+            }
+            _ => runs.push(index..index + 1),
+        }
+    }
+
+    let count = runs.len();
+    let is_fixed = |run: usize| fixed[runs[run].start];
+    let target_of = |run: usize| required_bank_angle(params, track_list[runs[run].start].curvature);
+
+    let mut banked = vec![];
+    for (run, range) in runs.iter().enumerate() {
+        if is_fixed(run) {
+            banked.extend(range.clone().map(|index| (index, track_list[index])));
+            continue;
+        }
+
+        let previous = match run {
+            0 if closed => Some(count - 1),
+            0 => None,
+            _ => Some(run - 1),
+        };
+        let next = match run + 1 {
+            next if next < count => Some(next),
+            _ if closed => Some(0),
+            _ => None,
+        };
+
+        let straight = track_list[range.start].curvature == 0.;
+        let target = target_of(run);
+        let from = previous.map_or(target, |previous| {
+            if is_fixed(previous) {
+                track_list[runs[previous].end - 1].end_bank_angle
+            } else {
+                target_of(previous)
+            }
+        });
+        let to = next.map_or(target, |next| {
+            if is_fixed(next) {
+                track_list[runs[next].start].start_bank_angle
+            } else {
+                target_of(next)
+            }
+        });
+
+        // straights ramp to the curves next to them, curves only ramp to other curves and fixed
+        // elements
+        let ramp_in = from != target
+            && (straight
+                || previous.is_some_and(|previous| {
+                    is_fixed(previous) || track_list[runs[previous].start].curvature != 0.
+                }));
+        let ramp_out = to != target && (straight || next.is_some_and(is_fixed));
+
+        let lengths: Vec<f32> = range
+            .clone()
+            .map(|index| element_length(&track_list[index]))
+            .collect();
+        let length: f32 = lengths.iter().sum();
+        let ramps = (ramp_in as usize + ramp_out as usize).max(1);
+        let runout = params.runout_length.min(length / ramps as f32);
+        let ramp_in_end = if ramp_in { runout } else { 0. };
+        let ramp_out_start = if ramp_out { length - runout } else { length };
+
+        let bank_at = |s: f32, middle: f32| {
+            if middle < ramp_in_end {
+                from + (target - from) * (s / ramp_in_end).clamp(0., 1.)
+            } else if middle > ramp_out_start {
+                target + (to - target) * ((s - ramp_out_start) / runout).clamp(0., 1.)
+            } else {
+                target
+            }
+        };
+
+        let mut s0 = 0.;
+        for (index, element_length) in range.clone().zip(lengths) {
+            let track = &track_list[index];
+            let s1 = s0 + element_length;
+
+            let mut cuts = vec![s0];
+            for cut in [ramp_in_end, ramp_out_start] {
+                if cut > s0 + MIN_PIECE_LENGTH
+                    && cut < s1 - MIN_PIECE_LENGTH
+                    && cuts.last() != Some(&cut)
+                {
+                    cuts.push(cut);
+                }
+            }
+            cuts.push(s1);
+
+            for piece in cuts.windows(2) {
+                let middle = (piece[0] + piece[1]) / 2.;
+                let ratio = if element_length > 0. {
+                    (piece[1] - piece[0]) / element_length
+                } else {
+                    1.
+                };
+
+                banked.push((
+                    index,
+                    TrackElement2D {
+                        curve_angle: track.curve_angle * ratio,
+                        length: track.length * ratio,
+                        start_bank_angle: bank_at(piece[0], middle),
+                        end_bank_angle: bank_at(piece[1], middle),
+                        ..*track
+                    },
+                ));
+            }
+
+            s0 = s1;
+        }
+    }
+
+    banked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_same_list(a: &[TrackElement2D], b: &[TrackElement2D]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert_eq!(a.curvature, b.curvature);
+            assert!((a.curve_angle - b.curve_angle).abs() < 1e-3, "{a:?} {b:?}");
+            assert!((a.length - b.length).abs() < 1e-3, "{a:?} {b:?}");
+            assert!(
+                (a.start_bank_angle - b.start_bank_angle).abs() < 1e-3,
+                "{a:?} {b:?}"
+            );
+            assert!(
+                (a.end_bank_angle - b.end_bank_angle).abs() < 1e-3,
+                "{a:?} {b:?}"
+            );
+        }
+    }
+
+    const STRAIGHT: TrackElement2D = TrackElement2D {
+        curvature: 0.,
+        curve_angle: 0.,
+        start_bank_angle: 0.,
+        end_bank_angle: 0.,
+        width: 8.,
+        length: 100.,
+        grade: 0.,
+        bank_pivot: crate::components::BankPivot::InsideEdge,
+    };
+
+    fn curve(curvature: f32, curve_angle: f32) -> TrackElement2D {
+        TrackElement2D {
+            curvature,
+            curve_angle,
+            length: 0.,
+            ..STRAIGHT
+        }
+    }
+
+    fn elements(banked: &[(usize, TrackElement2D)]) -> Vec<TrackElement2D> {
+        banked.iter().map(|(_, track)| *track).collect()
+    }
+
+    #[test]
+    fn banking_twice_gives_the_same_list() {
+        // a tight curve straight into a wide one in the other direction
+        let track_list = [
+            STRAIGHT,
+            curve(1. / 40., 90.),
+            curve(-1. / 120., 60.),
+            STRAIGHT,
+        ];
+        let params = BankingParams::default();
+
+        for closed in [false, true] {
+            let once = elements(&apply_banking(&params, &track_list, &[false; 4], closed));
+            let fixed = vec![false; once.len()];
+            let twice = elements(&apply_banking(&params, &once, &fixed, closed));
+
+            assert!(once.len() > track_list.len());
+            assert_same_list(&once, &twice);
+        }
+    }
+
+    #[test]
+    fn elements_are_not_merged() {
+        // two straights and two halves of a curve, authored separately
+        let track_list = [
+            STRAIGHT,
+            STRAIGHT,
+            curve(1. / 40., 45.),
+            curve(1. / 40., 45.),
+        ];
+        let banked = apply_banking(&BankingParams::default(), &track_list, &[false; 4], false);
+
+        // only the straight next to the curve is split for the runout
+        let indices: Vec<usize> = banked.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indices, [0, 1, 1, 2, 3]);
+        assert_eq!(banked[0].1, STRAIGHT);
+    }
+
+    #[test]
+    fn fixed_elements_keep_their_banking() {
+        let banked_straight = TrackElement2D {
+            start_bank_angle: 5.,
+            end_bank_angle: -5.,
+            ..STRAIGHT
+        };
+        let track_list = [STRAIGHT, banked_straight, curve(1. / 40., 90.), STRAIGHT];
+        let fixed = [false, true, false, false];
+        let banked = apply_banking(&BankingParams::default(), &track_list, &fixed, false);
+
+        let pieces: Vec<&TrackElement2D> = banked
+            .iter()
+            .filter(|(index, _)| *index == 1)
+            .map(|(_, track)| track)
+            .collect();
+        assert_eq!(pieces, [&banked_straight]);
+
+        // the curve ramps in from the bank the fixed element ends with
+        let curve_start = banked.iter().find(|(index, _)| *index == 2).unwrap().1;
+        assert_eq!(curve_start.start_bank_angle, -5.);
+    }
+}
@@ -0,0 +1,181 @@
+use bevy::{prelude::*, tasks::Task};
+
+use crate::{components::TrackElement2D, track::vertical::ElementProfile};
+
+pub const CLOSED_TRACK_TOLERANCE: f32 = 0.5; // max gap between track end and start of a loop
+pub const TRACKING_WINDOW: usize = 40; // centerline samples searched around the last position
+pub const LOST_DISTANCE: f32 = 20.; // search the whole track when further than this from it
+
+/// sample on the track centerline
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CenterlinePoint {
+    pub position: Vec3,
+    /// unit direction of travel
+    pub tangent: Vec3,
+    /// arc length from the start of the track
+    pub s: f32,
+    pub element_index: usize,
+    pub curvature: f32,
+    pub bank_angle: f32, // degrees
+    pub width: f32,
+}
+
+impl CenterlinePoint {
+    /// unit vector pointing to the left edge of the track in the ground plane
+    pub fn left(&self) -> Vec3 {
+        Vec3::Z.cross(self.tangent).normalize_or_zero()
+    }
+}
+
+/// centerline of the spawned track, rebuilt whenever the track is respawned
+#[derive(Resource, Default)]
+pub struct CenterlineResource {
+    pub points: Vec<CenterlinePoint>,
+    pub length: f32,
+    /// the end of the track meets its start
+    pub closed: bool,
+    /// track_layout_hash of the track list the centerline was sampled from
+    pub layout_hash: u64,
+}
+
+impl CenterlineResource {
+    /// wraps arc length onto the loop for closed tracks, clamps it for open tracks
+    pub fn wrap(&self, s: f32) -> f32 {
+        if self.length <= 0. {
+            0.
+        } else if self.closed {
+            s.rem_euclid(self.length)
+        } else {
+            s.clamp(0., self.length)
+        }
+    }
+
+    /// index of the sample at or before arc length s and the fraction to the next sample
+    pub fn index_at(&self, s: f32) -> (usize, f32) {
+        if self.points.len() < 2 {
+            return (0, 0.);
+        }
+
+        let s = self.wrap(s);
+        let next = self
+            .points
+            .partition_point(|point| point.s <= s)
+            .clamp(1, self.points.len() - 1);
+        let index = next - 1;
+
+        let ds = self.points[next].s - self.points[index].s;
+        let fraction = if ds > 0. {
+            (s - self.points[index].s) / ds
+        } else {
+            0.
+        };
+
+        (index, fraction)
+    }
+
+    /// interpolated centerline sample at arc length s
+    pub fn sample(&self, s: f32) -> CenterlinePoint {
+        if self.points.len() < 2 {
+            return self.points.first().copied().unwrap_or_default();
+        }
+
+        let (index, fraction) = self.index_at(s);
+        let a = &self.points[index];
+        let b = &self.points[index + 1];
+
+        CenterlinePoint {
+            position: a.position.lerp(b.position, fraction),
+            tangent: a.tangent.lerp(b.tangent, fraction).normalize_or_zero(),
+            s: self.wrap(s),
+            element_index: a.element_index,
+            curvature: a.curvature,
+            bank_angle: a.bank_angle + (b.bank_angle - a.bank_angle) * fraction,
+            width: a.width + (b.width - a.width) * fraction,
+        }
+    }
+
+    /// index of the sample closest to p in the ground plane
+    pub fn closest_index(&self, p: Vec3) -> Option<usize> {
+        self.points
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                let da = a.position.truncate().distance_squared(p.truncate());
+                let db = b.position.truncate().distance_squared(p.truncate());
+                da.total_cmp(&db)
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// like closest_index but only searches window samples either side of hint, so that
+    /// crossing or neighbouring parts of the track are not picked up
+    pub fn closest_index_near(&self, p: Vec3, hint: usize, window: usize) -> Option<usize> {
+        let count = self.points.len();
+        if count == 0 {
+            return None;
+        }
+
+        let hint = hint.min(count - 1) as i64;
+        let mut closest: Option<(usize, f32)> = None;
+
+        for offset in -(window as i64)..=(window as i64) {
+            let index = if self.closed {
+                (hint + offset).rem_euclid(count as i64) as usize
+            } else {
+                let index = hint + offset;
+                if index < 0 || index >= count as i64 {
+                    continue;
+                }
+                index as usize
+            };
+
+            let distance = self.points[index]
+                .position
+                .truncate()
+                .distance_squared(p.truncate());
+            if closest.is_none_or(|(_, closest_distance)| distance < closest_distance) {
+                closest = Some((index, distance));
+            }
+        }
+
+        closest.map(|(index, _)| index)
+    }
+}
+
+/// track meshes being generated on the async compute pool
+#[derive(Resource, Default)]
+pub struct TrackGenerationResource {
+    /// a build is running for target_list
+    pub pending: bool,
+    pub target_list: Vec<TrackElement2D>,
+    pub target_start: Transform,
+    pub target_profiles: Vec<ElementProfile>,
+    /// element index and mesh of the elements still being generated
+    pub tasks: Vec<(usize, Task<Mesh>)>,
+    /// element index and mesh of the finished elements, swapped in when all are done
+    pub meshes: Vec<(usize, Mesh)>,
+    /// element index and spawned mesh of the elements that keep their mesh
+    pub reused: Vec<(usize, Handle<Mesh>)>,
+    /// number of elements in the build
+    pub total: usize,
+}
+
+impl TrackGenerationResource {
+    /// dropping a task cancels it
+    pub fn cancel(&mut self) {
+        self.pending = false;
+        self.tasks.clear();
+        self.meshes.clear();
+        self.reused.clear();
+        self.total = 0;
+    }
+
+    /// fraction of the element meshes that are done
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.
+        } else {
+            (self.total - self.tasks.len()) as f32 / self.total as f32
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use std::f32::consts::FRAC_PI_2;
+
+use crate::components::{BankPivot, TrackElement2D};
+
+pub const SPIRAL_STEPS: usize = 4; // constant curvature arcs approximating each spiral
+const SOLVER_ITERATIONS: usize = 40;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum TurnDirection {
+    #[default]
+    Left,
+    Right,
+}
+
+impl TurnDirection {
+    /// sign of the curvature of a turn in this direction
+    pub fn sign(&self) -> f32 {
+        match self {
+            TurnDirection::Left => 1.,
+            TurnDirection::Right => -1.,
+        }
+    }
+
+    pub fn flip(&self) -> TurnDirection {
+        match self {
+            TurnDirection::Left => TurnDirection::Right,
+            TurnDirection::Right => TurnDirection::Left,
+        }
+    }
+}
+
+/// corner shape made of several track elements and edited as one item, lengths in ft and angles
+/// in degrees, corners are banked into the turn by bank_angle
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CompoundElement {
+    /// tight turn with spirals easing the curvature in and out
+    Hairpin {
+        radius: f32,
+        angle: f32,
+        spiral_length: f32,
+        bank_angle: f32,
+        direction: TurnDirection,
+    },
+    /// moves the track sideways by offset over length and keeps its heading
+    SBend {
+        offset: f32,
+        length: f32,
+        radius: f32,
+        bank_angle: f32,
+        direction: TurnDirection,
+    },
+    /// out by offset and back onto the same line over length
+    Chicane {
+        offset: f32,
+        length: f32,
+        radius: f32,
+        bank_angle: f32,
+        direction: TurnDirection,
+    },
+    /// alternating bends that keep the heading, count is the number of changes of direction
+    Esses {
+        count: usize,
+        angle: f32,
+        radius: f32,
+        bank_angle: f32,
+        direction: TurnDirection,
+    },
+}
+
+impl CompoundElement {
+    /// one of each kind, new compound elements start from these
+    pub const DEFAULTS: [CompoundElement; 4] = [
+        CompoundElement::Hairpin {
+            radius: 12.,
+            angle: 180.,
+            spiral_length: 10.,
+            bank_angle: 10.,
+            direction: TurnDirection::Left,
+        },
+        CompoundElement::SBend {
+            offset: 10.,
+            length: 60.,
+            radius: 30.,
+            bank_angle: 0.,
+            direction: TurnDirection::Left,
+        },
+        CompoundElement::Chicane {
+            offset: 8.,
+            length: 60.,
+            radius: 15.,
+            bank_angle: 0.,
+            direction: TurnDirection::Left,
+        },
+        CompoundElement::Esses {
+            count: 3,
+            angle: 60.,
+            radius: 25.,
+            bank_angle: 5.,
+            direction: TurnDirection::Left,
+        },
+    ];
+
+    /// the same shape turning the other way
+    pub fn mirrored(&self) -> CompoundElement {
+        let mut mirrored = *self;
+        match &mut mirrored {
+            CompoundElement::Hairpin { direction, .. }
+            | CompoundElement::SBend { direction, .. }
+            | CompoundElement::Chicane { direction, .. }
+            | CompoundElement::Esses { direction, .. } => *direction = direction.flip(),
+        }
+        mirrored
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompoundElement::Hairpin { .. } => "Hairpin",
+            CompoundElement::SBend { .. } => "S-Bend",
+            CompoundElement::Chicane { .. } => "Chicane",
+            CompoundElement::Esses { .. } => "Esses",
+        }
+    }
+
+    /// the track elements this is meshed as
+    pub fn expand(&self, width: f32) -> Vec<TrackElement2D> {
+        let mut elements = ElementChain::new(width);
+
+        match *self {
+            CompoundElement::Hairpin {
+                radius,
+                angle,
+                spiral_length,
+                bank_angle,
+                direction,
+            } => {
+                let curvature = 1. / radius.max(f32::EPSILON);
+                let bank_angle = -direction.sign() * bank_angle;
+
+                // each spiral turns by half of curvature * length
+                let total = angle.to_radians();
+                let spiral_length = spiral_length.clamp(0., total / curvature);
+                let step = spiral_length / SPIRAL_STEPS as f32;
+
+                if step > 0. {
+                    for index in 0..SPIRAL_STEPS {
+                        let ratio = (index as f32 + 0.5) / SPIRAL_STEPS as f32;
+                        let end_ratio = (index + 1) as f32 / SPIRAL_STEPS as f32;
+                        elements.arc(
+                            direction.sign() * curvature * ratio,
+                            curvature * ratio * step,
+                            bank_angle * end_ratio,
+                        );
+                    }
+                }
+
+                elements.arc(
+                    direction.sign() * curvature,
+                    total - curvature * spiral_length,
+                    bank_angle,
+                );
+
+                if step > 0. {
+                    for index in (0..SPIRAL_STEPS).rev() {
+                        let ratio = (index as f32 + 0.5) / SPIRAL_STEPS as f32;
+                        let end_ratio = index as f32 / SPIRAL_STEPS as f32;
+                        elements.arc(
+                            direction.sign() * curvature * ratio,
+                            curvature * ratio * step,
+                            bank_angle * end_ratio,
+                        );
+                    }
+                }
+            }
+            CompoundElement::SBend {
+                offset,
+                length,
+                radius,
+                bank_angle,
+                direction,
+            } => elements.s_bend(offset, length, radius, bank_angle, direction),
+            CompoundElement::Chicane {
+                offset,
+                length,
+                radius,
+                bank_angle,
+                direction,
+            } => {
+                elements.s_bend(offset, length / 2., radius, bank_angle, direction);
+                elements.s_bend(offset, length / 2., radius, bank_angle, direction.flip());
+            }
+            CompoundElement::Esses {
+                count,
+                angle,
+                radius,
+                bank_angle,
+                direction,
+            } => {
+                let curvature = 1. / radius.max(f32::EPSILON);
+                let count = count.max(1);
+
+                // half a bend at either end so the heading is kept
+                for index in 0..=count {
+                    let sign = if index % 2 == 0 {
+                        direction.sign()
+                    } else {
+                        -direction.sign()
+                    };
+                    let bend = if index == 0 || index == count {
+                        angle / 2.
+                    } else {
+                        angle
+                    };
+                    let end_bank_angle = if index == count {
+                        0.
+                    } else {
+                        -sign * bank_angle
+                    };
+
+                    elements.arc(sign * curvature, bend.to_radians(), end_bank_angle);
+                }
+            }
+        }
+
+        elements.track_list
+    }
+}
+
+/// track elements where each one starts with the bank angle the previous one ends with
+struct ElementChain {
+    track_list: Vec<TrackElement2D>,
+    width: f32,
+    bank_angle: f32,
+}
+
+impl ElementChain {
+    fn new(width: f32) -> ElementChain {
+        ElementChain {
+            track_list: vec![],
+            width,
+            bank_angle: 0.,
+        }
+    }
+
+    /// arc turning by angle (radians) with the sign of curvature
+    fn arc(&mut self, curvature: f32, angle: f32, end_bank_angle: f32) {
+        if angle <= 0. {
+            return;
+        }
+
+        self.track_list.push(TrackElement2D {
+            curvature,
+            curve_angle: angle.to_degrees(),
+            start_bank_angle: self.bank_angle,
+            end_bank_angle,
+            width: self.width,
+            length: 0.,
+            grade: 0.,
+            bank_pivot: BankPivot::InsideEdge,
+        });
+        self.bank_angle = end_bank_angle;
+    }
+
+    fn straight(&mut self, length: f32, end_bank_angle: f32) {
+        if length <= 0. {
+            return;
+        }
+
+        self.track_list.push(TrackElement2D {
+            curvature: 0.,
+            curve_angle: 0.,
+            start_bank_angle: self.bank_angle,
+            end_bank_angle,
+            width: self.width,
+            length,
+            grade: 0.,
+            bank_pivot: BankPivot::InsideEdge,
+        });
+        self.bank_angle = end_bank_angle;
+    }
+
+    /// two opposite arcs joined by a straight, the turn angle is solved so that the bend covers
+    /// length and moves sideways by offset, as far as the radius allows
+    fn s_bend(
+        &mut self,
+        offset: f32,
+        length: f32,
+        radius: f32,
+        bank_angle: f32,
+        direction: TurnDirection,
+    ) {
+        let radius = radius.max(f32::EPSILON);
+
+        // the straight between the arcs is (length - 2 r sin a) / cos a, the offset
+        // 2 r - 2 r / cos a + length tan a grows with the turn angle a while it is positive
+        let max_angle = (length / (2. * radius))
+            .min(1.)
+            .asin()
+            .min(FRAC_PI_2 - 1e-3);
+        let offset_at = |angle: f32| 2. * radius - 2. * radius / angle.cos() + length * angle.tan();
+
+        let (mut low, mut high) = (0., max_angle);
+        for _ in 0..SOLVER_ITERATIONS {
+            let angle = (low + high) / 2.;
+            if offset_at(angle) < offset.abs() {
+                low = angle;
+            } else {
+                high = angle;
+            }
+        }
+        let angle = (low + high) / 2.;
+        let straight = ((length - 2. * radius * angle.sin()) / angle.cos()).max(0.);
+
+        let sign = direction.sign() * offset.signum();
+        self.arc(sign / radius, angle, -sign * bank_angle);
+        self.straight(straight, sign * bank_angle);
+        self.arc(-sign / radius, angle, 0.);
+    }
+}
@@ -0,0 +1,236 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    components::TrackElement2D,
+    track::{
+        compound::TurnDirection,
+        shape::{set_end_point, set_radius_angle, set_straight, MIN_RADIUS},
+        systems::{element_end_transform, element_length, element_start_transform},
+    },
+};
+
+const MIN_SEGMENT_LENGTH: f32 = 1e-3; // ft, shorter arcs and straights are left out
+
+/// where a connection ends
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConnectTarget {
+    /// start of an element before the connection, 0 closes the circuit
+    ElementStart(usize),
+    /// world position in the ground plane and heading in degrees from +x, counterclockwise
+    Pose { position: Vec2, heading: f32 },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConnectMethod {
+    /// two arcs meeting with the same heading
+    Biarc,
+    /// the shortest of turn, straight, turn on arcs of radius
+    ArcLineArc { radius: f32 },
+}
+
+/// elements reaching a target pose from the end of the elements before them, refitted whenever
+/// those elements change
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Connection {
+    pub target: ConnectTarget,
+    pub method: ConnectMethod,
+}
+
+impl Default for Connection {
+    fn default() -> Connection {
+        Connection {
+            target: ConnectTarget::ElementStart(0),
+            method: ConnectMethod::Biarc,
+        }
+    }
+}
+
+impl Connection {
+    /// the same connection with a pose target mirrored across the line the track starts along
+    pub fn mirrored(&self, start_transform: &Transform) -> Connection {
+        let start_heading = heading(start_transform).to_degrees();
+        let mut mirrored = *self;
+        if let ConnectTarget::Pose { position, heading } = &mut mirrored.target {
+            *position = mirror_point(start_transform, *position);
+            *heading = 2. * start_heading - *heading;
+        }
+        mirrored
+    }
+
+    /// target position and heading (radians) in the world, none when the target element isn't
+    /// before start_index
+    pub fn target_pose(
+        &self,
+        start_transform: &Transform,
+        track_list: &[TrackElement2D],
+        start_index: usize,
+    ) -> Option<(Vec2, f32)> {
+        match self.target {
+            ConnectTarget::ElementStart(index) => {
+                if index >= start_index || index >= track_list.len() {
+                    return None;
+                }
+                let transform = element_start_transform(start_transform, track_list, index);
+                Some((transform.translation.truncate(), heading(&transform)))
+            }
+            ConnectTarget::Pose { position, heading } => Some((position, heading.to_radians())),
+        }
+    }
+
+    /// elements from start to the target, empty when there is no target or no fit
+    pub fn fit(
+        &self,
+        start_transform: &Transform,
+        track_list: &[TrackElement2D],
+        start_index: usize,
+        start_width: f32,
+        start_bank_angle: f32,
+    ) -> Vec<TrackElement2D> {
+        let Some((position, target_heading)) =
+            self.target_pose(start_transform, track_list, start_index)
+        else {
+            return vec![];
+        };
+
+        // target in the frame of the start, x forward and y to the left
+        let start = element_start_transform(start_transform, track_list, start_index);
+        let start_heading = heading(&start);
+        let point =
+            Vec2::from_angle(-start_heading).rotate(position - start.translation.truncate());
+        let angle = target_heading - start_heading;
+
+        let mut track_list = match self.method {
+            ConnectMethod::Biarc => biarc(point, angle),
+            ConnectMethod::ArcLineArc { radius } => arc_line_arc(point, angle, radius),
+        }
+        .unwrap_or_default();
+
+        // banking eases out over the first element
+        for (index, track) in track_list.iter_mut().enumerate() {
+            track.width = start_width;
+            if index == 0 {
+                track.start_bank_angle = start_bank_angle;
+            }
+        }
+        track_list
+    }
+}
+
+/// heading of a transform in the ground plane, radians from +x
+pub fn heading(transform: &Transform) -> f32 {
+    let forward = transform.rotation * Vec3::X;
+    forward.y.atan2(forward.x)
+}
+
+/// point mirrored across the line through the start along its heading
+pub fn mirror_point(start_transform: &Transform, point: Vec2) -> Vec2 {
+    let origin = start_transform.translation.truncate();
+    let along = Vec2::from_angle(heading(start_transform));
+    let offset = point - origin;
+    origin + 2. * offset.dot(along) * along - offset
+}
+
+/// two arcs from the origin heading along +x to point heading at angle (radians), with equal
+/// tangent lengths at both ends
+fn biarc(point: Vec2, angle: f32) -> Option<Vec<TrackElement2D>> {
+    let start_tangent = Vec2::X;
+    let end_tangent = Vec2::from_angle(angle);
+    let tangent_sum = start_tangent + end_tangent;
+    let cos = start_tangent.dot(end_tangent);
+
+    // |point - d (t0 + t1)| = 2 d solved for the tangent length d
+    let d = if 1. - cos < 1e-6 {
+        let along = point.dot(end_tangent);
+        if along <= 0. {
+            return None;
+        }
+        point.length_squared() / (4. * along)
+    } else {
+        let b = point.dot(tangent_sum);
+        (-b + (b * b + 2. * (1. - cos) * point.length_squared()).sqrt()) / (2. * (1. - cos))
+    };
+    if !d.is_finite() || d <= 0. {
+        return None;
+    }
+
+    // the arcs meet halfway between the ends of the tangents
+    let joint = (start_tangent * d + point - end_tangent * d) / 2.;
+
+    let mut first = TrackElement2D::default();
+    set_end_point(&mut first, joint);
+    let joint_transform = element_end_transform(&first);
+    let rest = joint_transform
+        .compute_affine()
+        .inverse()
+        .transform_point3(point.extend(0.))
+        .truncate();
+    let mut second = TrackElement2D::default();
+    set_end_point(&mut second, rest);
+
+    Some(
+        [first, second]
+            .into_iter()
+            .filter(|track| element_length(track) > MIN_SEGMENT_LENGTH)
+            .collect(),
+    )
+}
+
+/// shortest path of a turn, a straight and a turn on arcs of radius from the origin heading
+/// along +x to point heading at angle (radians)
+fn arc_line_arc(point: Vec2, angle: f32, radius: f32) -> Option<Vec<TrackElement2D>> {
+    let radius = radius.max(MIN_RADIUS);
+    let end_left = Vec2::from_angle(angle).perp();
+
+    [TurnDirection::Left, TurnDirection::Right]
+        .into_iter()
+        .flat_map(|first| [(first, TurnDirection::Left), (first, TurnDirection::Right)])
+        .filter_map(|(first, second)| {
+            // centers of the turning circles, to the side each turn is towards
+            let first_center = Vec2::Y * first.sign() * radius;
+            let second_center = point + end_left * second.sign() * radius;
+            let between = second_center - first_center;
+
+            // heading of the straight, offset from the line between the centers when the turns
+            // are in opposite directions
+            let (straight, straight_heading) = if first == second {
+                (between.length(), between.y.atan2(between.x))
+            } else {
+                let straight = (between.length_squared() - 4. * radius * radius).sqrt();
+                if straight.is_nan() {
+                    return None;
+                }
+                let offset = (2. * radius).atan2(straight) * first.sign();
+                (straight, between.y.atan2(between.x) + offset)
+            };
+
+            let first_angle = (straight_heading * first.sign()).rem_euclid(TAU);
+            let second_angle = ((angle - straight_heading) * second.sign()).rem_euclid(TAU);
+            let length = radius * (first_angle + second_angle) + straight;
+
+            Some((length, first, first_angle, straight, second, second_angle))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, first, first_angle, straight, second, second_angle)| {
+            let mut track_list = vec![TrackElement2D::default(); 3];
+            set_radius_angle(
+                &mut track_list[0],
+                Some(first),
+                radius,
+                first_angle.to_degrees(),
+            );
+            set_straight(&mut track_list[1], straight);
+            set_radius_angle(
+                &mut track_list[2],
+                Some(second),
+                radius,
+                second_angle.to_degrees(),
+            );
+
+            track_list
+                .into_iter()
+                .filter(|track| element_length(track) > MIN_SEGMENT_LENGTH)
+                .collect()
+        })
+}
@@ -0,0 +1,293 @@
+use bevy::prelude::*;
+use std::{f32::consts::TAU, fmt};
+
+use crate::{
+    components::{BankPivot, TrackElement2D},
+    track::{
+        components::CLOSED_TRACK_TOLERANCE,
+        systems::{element_end_transform, element_frame, element_length},
+    },
+};
+
+pub const GENERATOR_ATTEMPTS: usize = 500;
+pub const MIN_CORNER_ANGLE: f32 = 10.; // degrees, flatter polygon corners are rejected
+pub const MAX_RADIUS_FACTOR: f32 = 3.; // corner radii are drawn between the min radius and this times it
+pub const CLEARANCE_FACTOR: f32 = 1.5; // min distance between unrelated parts of the track in widths
+const LENGTH_ITERATIONS: usize = 4;
+const MIN_STRAIGHT_LENGTH: f32 = 0.01; // ft, shorter straights are left out
+
+/// constraints of a generated circuit, lengths in ft and angles in degrees
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CircuitParams {
+    pub seed: u64,
+    pub target_length: f32,
+    pub corners: usize,
+    pub min_radius: f32,
+    pub max_bank_angle: f32,
+    /// size of the rectangle the circuit has to fit in
+    pub area: Vec2,
+    pub width: f32,
+}
+
+impl Default for CircuitParams {
+    fn default() -> Self {
+        CircuitParams {
+            seed: 0,
+            target_length: 600.,
+            corners: 8,
+            min_radius: 10.,
+            max_bank_angle: 15.,
+            area: Vec2::new(250., 250.),
+            width: 8.,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum GeneratorError {
+    InvalidParams(&'static str),
+    /// every attempt for the seed broke a constraint
+    NoValidCircuit,
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeneratorError::InvalidParams(reason) => write!(f, "invalid parameters: {reason}"),
+            GeneratorError::NoValidCircuit => {
+                write!(
+                    f,
+                    "no circuit found for these constraints, try another seed"
+                )
+            }
+        }
+    }
+}
+
+/// generates a closed, non self-intersecting circuit that fits the constraints, the same seed
+/// and constraints always give the same track list
+pub fn generate_circuit(params: &CircuitParams) -> Result<Vec<TrackElement2D>, GeneratorError> {
+    if params.corners < 3 {
+        return Err(GeneratorError::InvalidParams(
+            "a circuit needs at least 3 corners",
+        ));
+    }
+    if params.width <= 0. || params.target_length <= 0. {
+        return Err(GeneratorError::InvalidParams(
+            "width and length must be positive",
+        ));
+    }
+    if params.min_radius < params.width {
+        return Err(GeneratorError::InvalidParams(
+            "min radius must be at least the track width",
+        ));
+    }
+    if params.area.x < 2. * params.min_radius || params.area.y < 2. * params.min_radius {
+        return Err(GeneratorError::InvalidParams(
+            "area is smaller than a single corner",
+        ));
+    }
+    if !(0. ..=60.).contains(&params.max_bank_angle) {
+        return Err(GeneratorError::InvalidParams(
+            "max bank angle must be 0 to 60 degrees",
+        ));
+    }
+
+    let mut rng = fastrand::Rng::with_seed(params.seed);
+
+    (0..GENERATOR_ATTEMPTS)
+        .find_map(|_| try_circuit(params, &mut rng))
+        .ok_or(GeneratorError::NoValidCircuit)
+}
+
+/// one random polygon with its corners rounded off, None if it breaks a constraint
+fn try_circuit(params: &CircuitParams, rng: &mut fastrand::Rng) -> Option<Vec<TrackElement2D>> {
+    let corners = params.corners;
+
+    // vertices sorted by angle around the centre form a star shaped polygon, which never crosses
+    // itself, the jitter is less than half the spacing so the order is kept
+    let mut shape: Vec<Vec2> = (0..corners)
+        .map(|index| {
+            let angle = (index as f32 + 0.8 * (rng.f32() - 0.5)) * TAU / corners as f32;
+            let radius = 0.55 + 0.45 * rng.f32();
+            Vec2::new(angle.cos(), angle.sin()) * params.area / 2. * radius
+        })
+        .collect();
+
+    // clockwise circuits too
+    if rng.bool() {
+        shape.reverse();
+    }
+
+    // the track starts along +X, so the first edge is turned onto it to keep the area in world space
+    let first_edge = shape[1] - shape[0];
+    let rotation = Vec2::new(first_edge.x, -first_edge.y).normalize();
+    let origin = shape[0];
+    let shape: Vec<Vec2> = shape
+        .iter()
+        .map(|point| rotation.rotate(*point - origin))
+        .collect();
+
+    // largest scale at which the rotated shape still fits the area
+    let (min, max) = shape.iter().fold(
+        (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+        |(min, max), point| (min.min(*point), max.max(*point)),
+    );
+    let max_scale = (params.area / (max - min).max(Vec2::splat(f32::EPSILON))).min_element();
+
+    let radii: Vec<f32> = (0..corners)
+        .map(|_| params.min_radius * (1. + (MAX_RADIUS_FACTOR - 1.) * rng.f32()))
+        .collect();
+
+    // the corner radii don't scale, so the length is matched by iterating
+    let perimeter: f32 = (0..corners)
+        .map(|index| shape[index].distance(shape[(index + 1) % corners]))
+        .sum();
+    let mut scale = params.target_length / perimeter;
+    let mut track_list = vec![];
+
+    for _ in 0..LENGTH_ITERATIONS {
+        if scale > max_scale {
+            return None;
+        }
+
+        let points: Vec<Vec2> = shape.iter().map(|point| *point * scale).collect();
+        track_list = round_corners(&points, &radii, params)?;
+
+        let length: f32 = track_list.iter().map(element_length).sum();
+        scale *= params.target_length / length;
+    }
+
+    if is_valid_circuit(&track_list, params) {
+        Some(track_list)
+    } else {
+        None
+    }
+}
+
+/// straights along the polygon edges joined by arcs of the given radii, banked into the corners
+fn round_corners(
+    points: &[Vec2],
+    radii: &[f32],
+    params: &CircuitParams,
+) -> Option<Vec<TrackElement2D>> {
+    let count = points.len();
+
+    // turn at each vertex, positive to the left
+    let turns: Vec<f32> = (0..count)
+        .map(|index| {
+            let incoming = points[index] - points[(index + count - 1) % count];
+            let outgoing = points[(index + 1) % count] - points[index];
+            incoming.angle_between(outgoing)
+        })
+        .collect();
+    if turns
+        .iter()
+        .any(|turn| turn.abs() < MIN_CORNER_ANGLE.to_radians())
+    {
+        return None;
+    }
+
+    // distance from the vertex to where the arc meets the edges
+    let tangents: Vec<f32> = (0..count)
+        .map(|index| radii[index] * (turns[index].abs() / 2.).tan())
+        .collect();
+
+    let mut track_list = vec![];
+
+    for index in 0..count {
+        let next = (index + 1) % count;
+
+        let straight = points[index].distance(points[next]) - tangents[index] - tangents[next];
+        if straight < 0. {
+            return None;
+        }
+        if straight > MIN_STRAIGHT_LENGTH {
+            track_list.push(TrackElement2D {
+                width: params.width,
+                length: straight,
+                ..default()
+            });
+        }
+
+        // tighter corners are banked more, each corner is split in two to bank in and out
+        let curvature = turns[next].signum() / radii[next];
+        let curve_angle = turns[next].abs().to_degrees() / 2.;
+        let bank_angle =
+            -turns[next].signum() * params.max_bank_angle * params.min_radius / radii[next];
+
+        track_list.push(TrackElement2D {
+            curvature,
+            curve_angle,
+            start_bank_angle: 0.,
+            end_bank_angle: bank_angle,
+            width: params.width,
+            length: 0.,
+            grade: 0.,
+            bank_pivot: BankPivot::InsideEdge,
+        });
+        track_list.push(TrackElement2D {
+            curvature,
+            curve_angle,
+            start_bank_angle: bank_angle,
+            end_bank_angle: 0.,
+            width: params.width,
+            length: 0.,
+            grade: 0.,
+            bank_pivot: BankPivot::InsideEdge,
+        });
+    }
+
+    Some(track_list)
+}
+
+/// the track ends where it starts, fits the area and no two parts of it come closer than the
+/// clearance
+fn is_valid_circuit(track_list: &[TrackElement2D], params: &CircuitParams) -> bool {
+    let step = params.width / 2.;
+    let mut samples: Vec<(f32, Vec2)> = vec![];
+    let mut start = Transform::IDENTITY;
+    let mut s = 0.;
+
+    for track in track_list {
+        let length = element_length(track);
+        let count = (length / step).ceil().max(1.) as usize;
+
+        for sample in 0..count {
+            let ratio = sample as f32 / count as f32;
+            let position = (start * element_frame(track, ratio)).translation;
+            samples.push((s + ratio * length, position.truncate()));
+        }
+
+        start = start * element_end_transform(track);
+        s += length;
+    }
+
+    if start.translation.length() > CLOSED_TRACK_TOLERANCE {
+        return false;
+    }
+
+    let (min, max) = samples.iter().fold(
+        (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+        |(min, max), (_, position)| (min.min(*position), max.max(*position)),
+    );
+    if (max - min + params.width).cmpgt(params.area).any() {
+        return false;
+    }
+
+    // closer along the track than half a turn of the tightest corner, the distance between two
+    // samples is bounded by the curvature limit
+    let neighbour_length = std::f32::consts::PI * params.min_radius;
+    let clearance = CLEARANCE_FACTOR * params.width;
+
+    for (index, (s_a, a)) in samples.iter().enumerate() {
+        for (s_b, b) in &samples[index + 1..] {
+            let along = (s_b - s_a).min(s - (s_b - s_a));
+            if along > neighbour_length && a.distance(*b) < clearance {
+                return false;
+            }
+        }
+    }
+
+    true
+}
@@ -1,14 +1,29 @@
 use bevy::prelude::*;
 
+pub mod banking;
+pub mod components;
+pub mod compound;
+pub mod connect;
+pub mod generator;
+pub mod shape;
 pub mod systems;
+pub mod vertical;
 
+use components::*;
 use systems::*;
 
 pub struct TrackPlugin;
 
 impl Plugin for TrackPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(default_track_list)
-            .add_startup_system(setup);
+        app.init_resource::<CenterlineResource>()
+            .init_resource::<TrackGenerationResource>()
+            .add_startup_system(default_track_list)
+            .add_startup_system(setup)
+            .add_system(update_track_sections)
+            .add_system(update_banking.after(update_track_sections))
+            .add_system(start_track_generation.after(update_banking))
+            .add_system(update_track.after(start_track_generation))
+            .add_system(update_centerline.after(update_track));
     }
 }
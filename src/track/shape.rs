@@ -0,0 +1,144 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{
+    components::TrackElement2D,
+    track::{compound::TurnDirection, systems::element_end_transform},
+};
+
+pub const MIN_RADIUS: f32 = 0.5; // ft, the tightest curve the curvature range allows
+pub const DEFAULT_RADIUS: f32 = 20.; // ft, radius shown for a straight until it is made a curve
+const STRAIGHT_TOLERANCE: f32 = 1e-4; // ft, end points closer to the x axis than this are straight
+
+/// how the shape of elements is entered, every form is converted to the curvature, curve angle
+/// and length of TrackElement2D
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum ElementInput {
+    /// the stored values, length only applies to straights and curve angle only to curves
+    #[default]
+    Curvature,
+    RadiusAngle,
+    RadiusLength,
+    /// end of the element relative to its start, the end heading follows from it
+    EndPoint,
+}
+
+impl ElementInput {
+    pub const ALL: [ElementInput; 4] = [
+        ElementInput::Curvature,
+        ElementInput::RadiusAngle,
+        ElementInput::RadiusLength,
+        ElementInput::EndPoint,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ElementInput::Curvature => "Curvature + Angle / Length",
+            ElementInput::RadiusAngle => "Radius + Angle",
+            ElementInput::RadiusLength => "Radius + Arc Length",
+            ElementInput::EndPoint => "End Point",
+        }
+    }
+}
+
+/// direction the element turns in, none for a straight
+pub fn turn_direction(track: &TrackElement2D) -> Option<TurnDirection> {
+    if track.curvature > 0. {
+        Some(TurnDirection::Left)
+    } else if track.curvature < 0. {
+        Some(TurnDirection::Right)
+    } else {
+        None
+    }
+}
+
+/// ft, infinite for a straight
+pub fn radius(track: &TrackElement2D) -> f32 {
+    1. / track.curvature.abs()
+}
+
+/// degrees the element turns by, the sign of the stored curve angle is ignored
+pub fn turn_angle(track: &TrackElement2D) -> f32 {
+    if track.curvature == 0. {
+        0.
+    } else {
+        track.curve_angle.abs()
+    }
+}
+
+/// end of the element in its start frame, x forward and y to the left
+pub fn end_point(track: &TrackElement2D) -> Vec2 {
+    element_end_transform(track).translation.truncate()
+}
+
+/// degrees the heading changes by, positive to the left
+pub fn heading_change(track: &TrackElement2D) -> f32 {
+    turn_angle(track) * track.curvature.signum()
+}
+
+/// straight of length, the curve angle is cleared
+pub fn set_straight(track: &mut TrackElement2D, length: f32) {
+    track.curvature = 0.;
+    track.curve_angle = 0.;
+    track.length = length.max(0.);
+}
+
+/// curve turning by angle (degrees) on radius, or a straight of the arc length without direction.
+/// length is set to the arc length so switching the element to a straight keeps its length.
+pub fn set_radius_angle(
+    track: &mut TrackElement2D,
+    direction: Option<TurnDirection>,
+    radius: f32,
+    angle: f32,
+) {
+    let radius = radius.max(MIN_RADIUS);
+    let angle = angle.abs();
+    let arc_length = radius * angle * PI / 180.;
+
+    match direction {
+        Some(direction) => {
+            track.curvature = direction.sign() / radius;
+            track.curve_angle = angle;
+            track.length = arc_length;
+        }
+        None => set_straight(track, arc_length),
+    }
+}
+
+/// curve of arc_length on radius, or a straight of arc_length without direction
+pub fn set_radius_length(
+    track: &mut TrackElement2D,
+    direction: Option<TurnDirection>,
+    radius: f32,
+    arc_length: f32,
+) {
+    let radius = radius.max(MIN_RADIUS);
+    let angle = (arc_length.abs() / radius).to_degrees();
+
+    set_radius_angle(track, direction, radius, angle);
+}
+
+/// the arc leaving the start along x that ends at point, a straight when point is on the x axis
+pub fn set_end_point(track: &mut TrackElement2D, point: Vec2) {
+    if point.y.abs() < STRAIGHT_TOLERANCE {
+        set_straight(track, point.x);
+        return;
+    }
+
+    // the circle through the start tangent to x has its center on the y axis at 1 / curvature
+    let curvature = 2. * point.y / point.length_squared();
+    let angle = 2. * point.y.abs().atan2(point.x);
+    let direction = if curvature > 0. {
+        TurnDirection::Left
+    } else {
+        TurnDirection::Right
+    };
+
+    set_radius_angle(
+        track,
+        Some(direction),
+        1. / curvature.abs(),
+        angle.to_degrees(),
+    );
+}
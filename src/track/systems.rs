@@ -1,12 +1,21 @@
 use bevy::{
     math::Vec4Swizzles,
     prelude::*,
-    render::{color::Color, mesh::Indices, render_resource::PrimitiveTopology},
+    render::{color::Color, mesh::Indices, primitives::Aabb, render_resource::PrimitiveTopology},
 };
 
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use futures_lite::future;
+
 use crate::{
     components::*,
+    spline::systems::fit_spline_section,
     systems::{MAX_TRACK_HEIGHT, MIN_TRACK_HEIGHT},
+    track::{
+        banking::apply_banking,
+        components::*,
+        vertical::{element_profiles, ElementProfile, VerticalCurve},
+    },
 };
 use std::f32::consts::PI;
 
@@ -15,6 +24,8 @@ pub const RAD_SUB_MAX_LEN: f32 = 0.3; // max subdivision radial delta (ft)
 pub const ARC_SUB_MAX_LEN: f32 = 0.3; // max subdivision arc length (ft)
 pub const MAX_CURVATURE: f32 = 3.;
 pub const TERRAIN_OFFSET: f32 = 25.; // how much the terrain extends past track
+pub const TRACK_START_POSITION: Vec3 = Vec3::new(0., -5., 0.);
+pub const CENTERLINE_SUB_MAX_LEN: f32 = 0.5; // max centerline sample spacing (ft)
 
 // track to terrain blending parameters
 pub const TRANSITION_WIDTH: f32 = 5.;
@@ -32,6 +43,8 @@ pub fn default_track_list(mut track_resource: ResMut<TrackResource>) {
         end_bank_angle: 0.,
         width: 8.,
         length: 10.,
+        grade: 0.,
+        bank_pivot: BankPivot::InsideEdge,
     };
     track_resource.track_list.push(track_element);
 
@@ -43,6 +56,8 @@ pub fn default_track_list(mut track_resource: ResMut<TrackResource>) {
         end_bank_angle: -30.,
         width: 8.,
         length: 20.,
+        grade: 0.,
+        bank_pivot: BankPivot::InsideEdge,
     };
     track_resource.track_list.push(track_element);
 
@@ -54,6 +69,8 @@ pub fn default_track_list(mut track_resource: ResMut<TrackResource>) {
         end_bank_angle: 0.,
         width: 8.,
         length: 0.,
+        grade: 0.,
+        bank_pivot: BankPivot::InsideEdge,
     };
     track_resource.track_list.push(track_element);
 
@@ -65,6 +82,8 @@ pub fn default_track_list(mut track_resource: ResMut<TrackResource>) {
         end_bank_angle: 0.,
         width: 8.,
         length: 20.,
+        grade: 0.,
+        bank_pivot: BankPivot::InsideEdge,
     };
     track_resource.track_list.push(track_element);
 
@@ -76,6 +95,8 @@ pub fn default_track_list(mut track_resource: ResMut<TrackResource>) {
         end_bank_angle: -30.,
         width: 8.,
         length: 20.,
+        grade: 0.,
+        bank_pivot: BankPivot::InsideEdge,
     };
     track_resource.track_list.push(track_element);
 
@@ -87,6 +108,8 @@ pub fn default_track_list(mut track_resource: ResMut<TrackResource>) {
         end_bank_angle: 0.,
         width: 8.,
         length: 0.,
+        grade: 0.,
+        bank_pivot: BankPivot::InsideEdge,
     };
     track_resource.track_list.push(track_element);
 
@@ -98,75 +121,329 @@ pub fn default_track_list(mut track_resource: ResMut<TrackResource>) {
         end_bank_angle: 0.,
         width: 8.,
         length: 10.,
+        grade: 0.,
+        bank_pivot: BankPivot::InsideEdge,
     };
     track_resource.track_list.push(track_element);
 }
 
 /// bevy system
 pub fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut global_resource: ResMut<GlobalResource>,
-    mut track_resource: ResMut<TrackResource>,
     mut mesh_resource: ResMut<MeshResource>,
 ) {
-    spawn_track(
-        &mut commands,
-        &mut meshes,
-        &mut materials,
-        &mut global_resource,
-        &mut track_resource,
-        &mut mesh_resource,
+    mesh_resource.track_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(1., 1., 1.),
+        unlit: false,
+        ..default()
+    });
+}
+
+/// bevy system
+/// regenerates the elements of every section, only writes the track resource on a difference
+pub fn update_track_sections(mut track_resource: ResMut<TrackResource>) {
+    if !track_resource.is_changed() || track_resource.sections.is_empty() {
+        return;
+    }
+
+    let mut track_list = track_resource.track_list.clone();
+    let mut sections = track_resource.sections.clone();
+
+    for index in 0..sections.len() {
+        let section = &mut sections[index];
+        section.start_index = section.start_index.min(track_list.len());
+        section.element_count = section
+            .element_count
+            .min(track_list.len() - section.start_index);
+
+        // the section continues the width and banking of the element before it
+        let previous = section
+            .start_index
+            .checked_sub(1)
+            .and_then(|index| track_list.get(index));
+        let start_width = previous.map_or(DEFAULT_TRACK_WIDTH, |track| track.width);
+        let start_bank_angle = previous.map_or(0., |track| track.end_bank_angle);
+
+        let generated = match &section.source {
+            SectionSource::Spline(control_points) => fit_spline_section(
+                control_points,
+                &element_start_transform(
+                    &track_resource.start_transform,
+                    &track_list,
+                    section.start_index,
+                ),
+                start_width,
+                start_bank_angle,
+            ),
+            SectionSource::Compound(compound) => compound.expand(start_width),
+            SectionSource::Connect(connection) => connection.fit(
+                &track_resource.start_transform,
+                &track_list,
+                section.start_index,
+                start_width,
+                start_bank_angle,
+            ),
+        };
+
+        let range = section.elements();
+        if track_list[range.clone()] == generated[..] {
+            continue;
+        }
+
+        // sections after this one move with its end
+        let removed = section.element_count;
+        let added = generated.len();
+        section.element_count = added;
+        track_list.splice(range, generated);
+        for later in sections.iter_mut().skip(index + 1) {
+            later.start_index = later.start_index + added - removed;
+        }
+    }
+
+    if track_list != track_resource.track_list {
+        track_resource.track_list = track_list;
+    }
+    if sections != track_resource.sections {
+        track_resource.sections = sections;
+    }
+}
+
+/// bevy system
+/// re-banks the plain elements for the design speed of the banking mode whenever the track
+/// changes, the sections keep their elements and move with the runouts split off before them
+pub fn update_banking(mut track_resource: ResMut<TrackResource>) {
+    let Some(params) = track_resource.banking else {
+        return;
+    };
+    if !track_resource.is_changed() {
+        return;
+    }
+
+    let track_list = &track_resource.track_list;
+    let fixed: Vec<bool> = (0..track_list.len())
+        .map(|index| track_resource.section_of(index).is_some())
+        .collect();
+    let banked = apply_banking(&params, track_list, &fixed, is_closed(track_list));
+
+    // where the first piece of each element, and the end of the list, went
+    let mut first_piece = vec![banked.len(); track_list.len() + 1];
+    for (piece, (index, _)) in banked.iter().enumerate().rev() {
+        first_piece[*index] = piece;
+    }
+
+    let banked_list: Vec<TrackElement2D> = banked.into_iter().map(|(_, track)| track).collect();
+    if banked_list == track_resource.track_list {
+        return;
+    }
+
+    let sections: Vec<TrackSection> = track_resource
+        .sections
+        .iter()
+        .map(|section| TrackSection {
+            start_index: first_piece[section.start_index.min(track_list.len())],
+            ..section.clone()
+        })
+        .collect();
+    track_resource.track_list = banked_list;
+    if sections != track_resource.sections {
+        track_resource.sections = sections;
+    }
+}
+
+/// bevy system
+/// starts generating meshes on the async compute pool for the elements no spawned mesh was built
+/// from, wherever they are in the list. An edit made mid-build replaces the running build, the
+/// tasks and finished meshes of the elements it didn't change are carried over, so an edit that
+/// changes the track every frame (a handle or control point drag) only rebuilds what it edits.
+pub fn start_track_generation(
+    track_resource: Res<TrackResource>,
+    mesh_resource: Res<MeshResource>,
+    mut generation_resource: ResMut<TrackGenerationResource>,
+) {
+    // nothing to compare until the track is edited or a build is swapped in
+    if !track_resource.is_changed() && !mesh_resource.is_changed() {
+        return;
+    }
+
+    let profiles = element_profiles(
+        &track_resource.start_transform,
+        &track_resource.track_list,
+        &track_resource.vertical_curve,
     );
+
+    // the running build is already for these parameters
+    if generation_resource.pending
+        && generation_resource.target_list == track_resource.track_list
+        && generation_resource.target_start == track_resource.start_transform
+        && generation_resource.target_profiles == profiles
+    {
+        return;
+    }
+
+    // running and finished meshes of the replaced build by the values they are built from
+    let generation_resource = &mut *generation_resource;
+    let target_list = std::mem::take(&mut generation_resource.target_list);
+    let target_profiles = std::mem::take(&mut generation_resource.target_profiles);
+    let target_key = |index: usize| (target_list[index], target_profiles[index]);
+    let mut running: Vec<((TrackElement2D, ElementProfile), Task<Mesh>)> = generation_resource
+        .tasks
+        .drain(..)
+        .map(|(index, task)| (target_key(index), task))
+        .collect();
+    let mut finished: Vec<((TrackElement2D, ElementProfile), Mesh)> = generation_resource
+        .meshes
+        .drain(..)
+        .map(|(index, mesh)| (target_key(index), mesh))
+        .collect();
+    generation_resource.cancel();
+
+    // back to what is already spawned
+    if mesh_resource.track_element_list == track_resource.track_list
+        && mesh_resource.start_transform == track_resource.start_transform
+        && mesh_resource.track_profile_list == profiles
+    {
+        return;
+    }
+
+    let task_pool = AsyncComputeTaskPool::get();
+
+    // spawned meshes by the values they were built from, an element that was only moved to
+    // another index keeps its mesh
+    let mut spawned: Vec<((&TrackElement2D, &ElementProfile), &Handle<Mesh>)> = mesh_resource
+        .track_element_list
+        .iter()
+        .zip(&mesh_resource.track_profile_list)
+        .zip(&mesh_resource.track_mesh_list)
+        .collect();
+
+    for (index, (track_element, profile)) in
+        track_resource.track_list.iter().zip(&profiles).enumerate()
+    {
+        if let Some(position) = spawned
+            .iter()
+            .position(|(key, _)| *key == (track_element, profile))
+        {
+            let (_, mesh_handle) = spawned.swap_remove(position);
+            generation_resource
+                .reused
+                .push((index, mesh_handle.clone()));
+            continue;
+        }
+
+        let key = (*track_element, *profile);
+        if let Some(position) = finished.iter().position(|(finished, _)| *finished == key) {
+            let (_, mesh) = finished.swap_remove(position);
+            generation_resource.meshes.push((index, mesh));
+            continue;
+        }
+        if let Some(position) = running.iter().position(|(running, _)| *running == key) {
+            let (_, task) = running.swap_remove(position);
+            generation_resource.tasks.push((index, task));
+            continue;
+        }
+
+        let track_element = *track_element;
+        let profile = *profile;
+        let task = task_pool.spawn(async move {
+            let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+            let mut end_transform = Transform::IDENTITY;
+            track_mesh_2d(&track_element, &profile, &mut mesh, &mut end_transform);
+            mesh
+        });
+        generation_resource.tasks.push((index, task));
+    }
+
+    // dropping the tasks left over cancels them
+    drop(running);
+
+    generation_resource.total = generation_resource.tasks.len() + generation_resource.meshes.len();
+    generation_resource.target_list = track_resource.track_list.clone();
+    generation_resource.target_start = track_resource.start_transform;
+    generation_resource.target_profiles = profiles;
+    generation_resource.pending = true;
 }
 
-/// spawns track elements
-pub fn spawn_track(
-    commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<StandardMaterial>>,
-    global_resource: &mut ResMut<GlobalResource>,
-    track_resource: &mut ResMut<TrackResource>,
-    mesh_resource: &mut ResMut<MeshResource>,
+/// bevy system
+/// collects the generated meshes and, once all of them are done, swaps them in together and
+/// moves the reused meshes to the new start of their element
+pub fn update_track(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut global_resource: ResMut<GlobalResource>,
+    mut generation_resource: ResMut<TrackGenerationResource>,
+    mut mesh_resource: ResMut<MeshResource>,
+    mut element_query: Query<(Entity, &Handle<Mesh>, &mut Transform), With<TrackElement>>,
 ) {
-    let mut prev_transform = Transform::from_translation(Vec3::new(0., -5., 0.));
+    if !generation_resource.pending {
+        return;
+    }
 
-    let track_material_handle = materials.add(StandardMaterial {
-        base_color: Color::rgb(1., 1., 1.),
-        unlit: false,
-        ..default()
-    });
+    let generation_resource = &mut *generation_resource;
+    let mut index = 0;
+    while index < generation_resource.tasks.len() {
+        let (element_index, task) = &mut generation_resource.tasks[index];
+        match future::block_on(future::poll_once(task)) {
+            Some(mesh) => {
+                generation_resource.meshes.push((*element_index, mesh));
+                drop(generation_resource.tasks.swap_remove(index));
+            }
+            None => index += 1,
+        }
+    }
+
+    if !generation_resource.tasks.is_empty() {
+        return;
+    }
+
+    let mesh_resource = &mut *mesh_resource;
+    let mut prev_transform = generation_resource.target_start;
+    let mut rebuilt_mesh_list = vec![];
+
+    // the terrain covers the start and the end of every element
+    global_resource.x_min = -TERRAIN_OFFSET;
+    global_resource.x_max = TERRAIN_OFFSET;
+    global_resource.y_min = -TERRAIN_OFFSET;
+    global_resource.y_max = TERRAIN_OFFSET;
 
-    // clear each list each render
-    mesh_resource.track_mesh_list.clear();
     mesh_resource.track_mesh_transform_list.clear();
 
-    // iterate through track list, spawn to scene, and update track extent
-    for track_element in &track_resource.track_list {
-        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
-        let mut new_transform = Transform::IDENTITY;
-        track_mesh_2d(&track_element, &mut mesh, &mut new_transform);
+    let element_count = generation_resource.target_list.len();
+    let mut mesh_list: Vec<Option<Handle<Mesh>>> = vec![None; element_count];
+    for (index, mesh_handle) in generation_resource.reused.drain(..) {
+        mesh_list[index] = Some(mesh_handle);
+    }
 
-        let mesh_handle = meshes.add(mesh);
+    // handles of the meshes that are no longer used take the rebuilt ones, so their entities
+    // pick up the new mesh
+    let mut free_mesh_list: Vec<Handle<Mesh>> = mesh_resource
+        .track_mesh_list
+        .drain(..)
+        .filter(|mesh_handle| !mesh_list.iter().flatten().any(|used| used == mesh_handle))
+        .collect();
+
+    for (index, mesh) in generation_resource.meshes.drain(..) {
+        mesh_list[index] = Some(match free_mesh_list.pop() {
+            Some(mesh_handle) => {
+                if let Some(old_mesh) = meshes.get_mut(&mesh_handle) {
+                    *old_mesh = mesh;
+                }
+                rebuilt_mesh_list.push(mesh_handle.clone());
+                mesh_handle
+            }
+            None => meshes.add(mesh),
+        });
+    }
+
+    for mesh_handle in free_mesh_list {
+        meshes.remove(mesh_handle);
+    }
+    mesh_resource.track_mesh_list = mesh_list.into_iter().flatten().collect();
 
+    for track_element in &generation_resource.target_list {
         // store values to be used in render to texture pass
-        mesh_resource.track_mesh_list.push(mesh_handle.clone());
         mesh_resource.track_mesh_transform_list.push(prev_transform);
 
-        commands.spawn((
-            PbrBundle {
-                mesh: mesh_handle.clone(),
-                material: track_material_handle.clone(),
-                transform: prev_transform,
-                visibility: Visibility::Hidden,
-                ..default()
-            },
-            TrackElement,
-            MyEntity,
-        ));
-
-        prev_transform = prev_transform * new_transform;
+        prev_transform = prev_transform * element_end_transform(track_element);
 
         // update track extent (terrain plane size)
         let p: Vec3 = (prev_transform.compute_matrix() * Vec4::new(0., 0., 0., 1.)).xyz();
@@ -187,12 +464,89 @@ pub fn spawn_track(
             global_resource.y_min = p.y - TERRAIN_OFFSET;
         }
     }
+
+    mesh_resource.track_element_list = std::mem::take(&mut generation_resource.target_list);
+    mesh_resource.start_transform = generation_resource.target_start;
+    mesh_resource.track_profile_list = std::mem::take(&mut generation_resource.target_profiles);
+    generation_resource.pending = false;
+
+    // move the spawned elements, bounds are recomputed for entities without one
+    let mut spawned = vec![false; element_count];
+    for (entity, mesh_handle, mut transform) in element_query.iter_mut() {
+        match mesh_resource
+            .track_mesh_list
+            .iter()
+            .position(|handle| handle == mesh_handle)
+        {
+            Some(index) => {
+                spawned[index] = true;
+                if *transform != mesh_resource.track_mesh_transform_list[index] {
+                    *transform = mesh_resource.track_mesh_transform_list[index];
+                }
+                if rebuilt_mesh_list.contains(mesh_handle) {
+                    commands.entity(entity).remove::<Aabb>();
+                }
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for index in (0..element_count).filter(|index| !spawned[*index]) {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh_resource.track_mesh_list[index].clone(),
+                material: mesh_resource.track_material.clone(),
+                transform: mesh_resource.track_mesh_transform_list[index],
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            TrackElement,
+            MyEntity,
+        ));
+    }
+}
+
+/// end of the element relative to its start, the same transform track_mesh_2d returns
+pub fn element_end_transform(track: &TrackElement2D) -> Transform {
+    let length = element_length(track);
+
+    // no mesh nodes are generated for these
+    if track.curvature > MAX_CURVATURE || (length / ARC_SUB_MAX_LEN).ceil() < 1. {
+        return Transform::IDENTITY;
+    }
+
+    let mut arc_transform = Transform::IDENTITY;
+    create_arc_transform(&mut arc_transform, track, length, 1.);
+    arc_transform
+}
+
+/// whether the track list ends where it starts
+pub fn is_closed(track_list: &[TrackElement2D]) -> bool {
+    !track_list.is_empty()
+        && element_start_transform(&Transform::IDENTITY, track_list, track_list.len())
+            .translation
+            .length()
+            < CLOSED_TRACK_TOLERANCE
+}
+
+/// start of the element at index, the end of the elements before it
+pub fn element_start_transform(
+    start_transform: &Transform,
+    track_list: &[TrackElement2D],
+    index: usize,
+) -> Transform {
+    track_list[..index.min(track_list.len())]
+        .iter()
+        .fold(*start_transform, |start, track| {
+            start * element_end_transform(track)
+        })
 }
 
 /// creates track element mesh in passed mesh and passed transform ends as start position of
-/// next track element
+/// next track element, the vertical curves of profile are added on top of the element grade
 pub fn track_mesh_2d(
     track: &TrackElement2D,
+    profile: &ElementProfile,
     track_mesh: &mut Mesh,
     arc_transform: &mut Transform,
 ) -> bool {
@@ -208,14 +562,7 @@ pub fn track_mesh_2d(
     } // curve is below min radius
 
     // determine length
-    let length: f32;
-    if track.curvature == 0. {
-        // straight
-        length = track.length;
-    } else {
-        // curve
-        length = (2. * PI * track.curve_angle / 360. / track.curvature).abs();
-    }
+    let length = element_length(track);
 
     // mesh density
     let num_arc_nodes = (length / ARC_SUB_MAX_LEN).ceil() as u32;
@@ -242,6 +589,16 @@ pub fn track_mesh_2d(
         create_bank_transform(&mut bank_transform, track, length_ratio);
         let bank_matrix = bank_transform.compute_matrix();
 
+        // vertical curve, raises the section and shears its normals along the slope
+        let s = length_ratio * length;
+        let vertical_offset = profile.offset(s);
+        let slope_matrix = Mat4::from_cols(
+            Vec4::new(1., 0., profile.slope(s), 0.),
+            Vec4::Y,
+            Vec4::Z,
+            Vec4::W,
+        );
+
         let mut curr_width: f32;
         let mut vertex_color_red: f32; // represents track to terrain blend
 
@@ -284,14 +641,19 @@ pub fn track_mesh_2d(
 
             // define position
             let p = Vec4::new(0., -curr_width, 0., 1.);
-            let p: Vec3 = (arc_matrix * bank_matrix * p).xyz();
+            let p: Vec3 = (arc_matrix * bank_matrix * p).xyz() + Vec3::Z * vertical_offset;
 
-            let vertex_color_green =
-                (p.z - MIN_TRACK_HEIGHT) / (MAX_TRACK_HEIGHT - MIN_TRACK_HEIGHT);
+            // height in the world, the mesh is placed at the start of the element tangent
+            let vertex_color_green = (profile.start_elevation + p.z - MIN_TRACK_HEIGHT)
+                / (MAX_TRACK_HEIGHT - MIN_TRACK_HEIGHT);
 
             // define normals
             let n = Vec4::new(0., 0., 1., 1.);
-            let n: Vec3 = ((arc_matrix * bank_matrix).inverse().transpose() * n).xyz();
+            let n: Vec3 = ((arc_matrix * slope_matrix * bank_matrix)
+                .inverse()
+                .transpose()
+                * n)
+                .xyz();
 
             // define colors
             let c = Color::rgb(vertex_color_red, vertex_color_green, 0.);
@@ -327,7 +689,173 @@ pub fn track_mesh_2d(
     true //return
 }
 
-/// creates transformation matrix for current length on arc length
+/// arc length of track element, straights use length and curves use curve angle
+pub fn element_length(track: &TrackElement2D) -> f32 {
+    if track.curvature == 0. {
+        // straight
+        track.length
+    } else {
+        // curve
+        (2. * PI * track.curve_angle / 360. / track.curvature).abs()
+    }
+}
+
+/// banked centerline transform at length_ratio along the element, relative to the element start.
+/// it follows the element grade, the vertical curves at its ends are left out.
+pub fn element_frame(track: &TrackElement2D, length_ratio: f32) -> Transform {
+    let mut arc_transform = Transform::IDENTITY;
+    let mut bank_transform = Transform::IDENTITY;
+
+    create_arc_transform(
+        &mut arc_transform,
+        track,
+        element_length(track),
+        length_ratio,
+    );
+    create_bank_transform(&mut bank_transform, track, length_ratio);
+
+    arc_transform * bank_transform
+}
+
+/// bevy system
+/// resamples the centerline when the track has been respawned
+pub fn update_centerline(
+    track_resource: Res<TrackResource>,
+    mesh_resource: Res<MeshResource>,
+    mut centerline_resource: ResMut<CenterlineResource>,
+) {
+    if !mesh_resource.is_changed() {
+        return;
+    }
+
+    let points = track_centerline(
+        &track_resource.start_transform,
+        &track_resource.track_list,
+        &track_resource.vertical_curve,
+    );
+
+    centerline_resource.length = points.last().map_or(0., |point| point.s);
+    centerline_resource.closed = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => {
+            points.len() > 2 && first.position.distance(last.position) < CLOSED_TRACK_TOLERANCE
+        }
+        _ => false,
+    };
+    centerline_resource.points = points;
+    centerline_resource.layout_hash = track_layout_hash(
+        &track_resource.start_transform,
+        &track_resource.track_list,
+        &track_resource.vertical_curve,
+    );
+}
+
+/// FNV-1a hash of the track geometry and where it starts, stable between runs so it can be stored
+/// in files
+pub fn track_layout_hash(
+    start_transform: &Transform,
+    track_list: &[TrackElement2D],
+    vertical_curve: &VerticalCurve,
+) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let profiles = element_profiles(start_transform, track_list, vertical_curve);
+
+    // a reversed layout can have the same elements in a different place
+    let start = start_transform
+        .translation
+        .to_array()
+        .into_iter()
+        .chain(start_transform.rotation.to_array());
+    for value in start {
+        for byte in value.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    for (track, profile) in track_list.iter().zip(profiles) {
+        // element length rather than length and curve angle, each is ignored by one element type
+        let values = [
+            track.curvature,
+            element_length(track),
+            track.start_bank_angle,
+            track.end_bank_angle,
+            track.width,
+            track.grade,
+            profile.end.length,
+            track.bank_pivot as u8 as f32,
+        ];
+
+        for value in values {
+            for byte in value.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+    }
+
+    hash
+}
+
+/// samples the centerline of the track list in world space with its vertical curves, the last
+/// sample is the end of the last track element
+pub fn track_centerline(
+    start_transform: &Transform,
+    track_list: &[TrackElement2D],
+    vertical_curve: &VerticalCurve,
+) -> Vec<CenterlinePoint> {
+    let profiles = element_profiles(start_transform, track_list, vertical_curve);
+    let mut points = vec![];
+    let mut prev_transform = *start_transform;
+    let mut s = 0.;
+
+    let mut arc_transform = Transform::IDENTITY;
+    let mut bank_transform = Transform::IDENTITY;
+
+    for (element_index, (track, profile)) in track_list.iter().zip(&profiles).enumerate() {
+        let length = element_length(track);
+        let num_samples = (length / CENTERLINE_SUB_MAX_LEN).ceil().max(1.) as u32;
+        let is_last = element_index == track_list.len() - 1;
+
+        // the end of each element is the start of the next, except for the last one
+        let sample_count = if is_last {
+            num_samples + 1
+        } else {
+            num_samples
+        };
+
+        for sample in 0..sample_count {
+            let length_ratio = sample as f32 / num_samples as f32;
+
+            create_arc_transform(&mut arc_transform, track, length, length_ratio);
+            create_bank_transform(&mut bank_transform, track, length_ratio);
+
+            let centerline_transform = prev_transform * arc_transform * bank_transform;
+            let s_element = length_ratio * length;
+            let heading = prev_transform.rotation * arc_transform.rotation * Vec3::X;
+            let tangent = (heading + Vec3::Z * profile.slope(s_element)).normalize();
+
+            points.push(CenterlinePoint {
+                position: centerline_transform.translation + Vec3::Z * profile.offset(s_element),
+                tangent,
+                s: s + length_ratio * length,
+                element_index,
+                curvature: track.curvature,
+                bank_angle: track.start_bank_angle
+                    + smoothstep(length_ratio) * (track.end_bank_angle - track.start_bank_angle),
+                width: track.width,
+            });
+        }
+
+        // move to the start of the next element
+        create_arc_transform(&mut arc_transform, track, length, 1.);
+        prev_transform = prev_transform * arc_transform;
+        s += length;
+    }
+
+    points
+}
+
+/// creates transformation matrix for current length on arc length, climbing along the grade
 fn create_arc_transform(
     transform: &mut Transform,
     track: &TrackElement2D,
@@ -335,10 +863,11 @@ fn create_arc_transform(
     length_ratio: f32,
 ) {
     let curr_length = length_ratio * length;
+    let z = track.grade / 100. * curr_length;
 
     if track.curvature == 0. {
         // straight
-        let translation = Transform::from_xyz(curr_length, 0., 0.);
+        let translation = Transform::from_xyz(curr_length, 0., z);
         *transform = Transform::IDENTITY * translation;
     } else {
         // curve
@@ -346,7 +875,6 @@ fn create_arc_transform(
 
         let x = theta.sin() / track.curvature.abs();
         let y = (1. - theta.cos()) / track.curvature.abs();
-        let z = 0.;
 
         let translation: Transform;
         let rotation: Transform;
@@ -373,7 +901,11 @@ fn create_bank_transform(transform: &mut Transform, track: &TrackElement2D, leng
 
     let banking = Transform::from_rotation(Quat::from_rotation_x(curr_bank_angle));
 
-    let height_offset = (track.width / 2. * curr_bank_angle.sin()).abs();
+    // keeps the pivot at the height of the element tangent
+    let height_offset =
+        track
+            .bank_pivot
+            .centerline_height(track.width, curr_bank_angle, track.curvature);
     let translation = Transform::from_translation(Vec3::new(0., 0., height_offset));
 
     *transform = translation * banking;
@@ -381,7 +913,7 @@ fn create_bank_transform(transform: &mut Transform, track: &TrackElement2D, leng
 
 /// GLSL style smoothstep function with edges at 0 and 1
 /// example: https://en.wikipedia.org/wiki/Smoothstep
-fn smoothstep(value: f32) -> f32 {
+pub fn smoothstep(value: f32) -> f32 {
     if value < 0.0 {
         return 0.0;
     } else if value > 1.0 {
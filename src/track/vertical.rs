@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::TrackElement2D,
+    track::systems::{element_end_transform, element_length, is_closed, smoothstep},
+};
+
+pub const EYE_HEIGHT: f32 = 3.5; // ft, driver eye above the road for sight distance
+pub const OBJECT_HEIGHT: f32 = 2.; // ft, obstacle that has to be seen over a crest
+pub const REACTION_TIME: f32 = 2.5; // s
+pub const BRAKING_DECELERATION: f32 = 11.2; // ft/s^2
+pub const DEFAULT_VERTICAL_CURVE_LENGTH: f32 = 50.; // ft
+
+/// length of the parabolic vertical curves smoothing the grade breaks between elements
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VerticalCurve {
+    /// ft of curve per percent of grade change
+    KValue(f32),
+    /// ft
+    Length(f32),
+}
+
+impl Default for VerticalCurve {
+    fn default() -> Self {
+        VerticalCurve::KValue(10.)
+    }
+}
+
+/// parabola centered on the joint between two elements
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct GradeBreak {
+    /// outgoing minus incoming grade, percent, negative for a crest
+    pub grade_change: f32,
+    /// ft, limited so it fits in half of both elements
+    pub length: f32,
+}
+
+impl GradeBreak {
+    /// height above the tangent at distance from the end of the curve, ft
+    fn offset(&self, distance: f32) -> f32 {
+        if self.length <= 0. || distance <= 0. {
+            return 0.;
+        }
+        self.grade_change / 100. / (2. * self.length) * distance * distance
+    }
+
+    /// change of the offset along the direction away from the joint
+    fn slope(&self, distance: f32) -> f32 {
+        if self.length <= 0. || distance <= 0. {
+            return 0.;
+        }
+        self.grade_change / 100. / self.length * distance
+    }
+
+    /// sight distance over a crest for EYE_HEIGHT and OBJECT_HEIGHT, infinite for a sag
+    pub fn sight_distance(&self) -> f32 {
+        let change = -self.grade_change;
+        if change <= 0. || self.length <= 0. {
+            return f32::INFINITY;
+        }
+
+        let heights = 200. * (EYE_HEIGHT.sqrt() + OBJECT_HEIGHT.sqrt()).powi(2);
+        let within = (heights * self.length / change).sqrt();
+        if within <= self.length {
+            within
+        } else {
+            (self.length + heights / change) / 2.
+        }
+    }
+}
+
+/// vertical alignment of one element, the element climbs along its grade and the vertical
+/// curves at its ends are added on top
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct ElementProfile {
+    /// world height of the start of the element tangent
+    pub start_elevation: f32,
+    pub grade: f32, // percent
+    pub length: f32,
+    pub start: GradeBreak,
+    pub end: GradeBreak,
+    /// height the centerline is moved by at the start and the end of the element to meet the
+    /// neighbouring ones, when their bank pivots or widths differ under a bank. Each side of a
+    /// joint takes half of the step and eases it out over the element like the banking.
+    pub start_pivot_offset: f32,
+    pub end_pivot_offset: f32,
+}
+
+impl ElementProfile {
+    /// height of the vertical curves and the pivot step above the element tangent at distance s
+    /// along it
+    pub fn offset(&self, s: f32) -> f32 {
+        self.start.offset(self.start.length / 2. - s)
+            + self.end.offset(s - (self.length - self.end.length / 2.))
+            + self.start_pivot_offset * (1. - smoothstep(self.length_ratio(s)))
+            + self.end_pivot_offset * smoothstep(self.length_ratio(s))
+    }
+
+    /// rise over run at distance s along the element
+    pub fn slope(&self, s: f32) -> f32 {
+        let ratio = self.length_ratio(s);
+        let pivot_slope = if self.length > 0. && (0. ..=1.).contains(&ratio) {
+            (self.end_pivot_offset - self.start_pivot_offset) * 6. * ratio * (1. - ratio)
+                / self.length
+        } else {
+            0.
+        };
+
+        self.grade / 100. - self.start.slope(self.start.length / 2. - s)
+            + self.end.slope(s - (self.length - self.end.length / 2.))
+            + pivot_slope
+    }
+
+    fn length_ratio(&self, s: f32) -> f32 {
+        if self.length > 0. {
+            s / self.length
+        } else {
+            1.
+        }
+    }
+}
+
+/// vertical curve at every grade break of the track list, between each element and the next. A
+/// closed track also has one between the last element and the first.
+pub fn grade_breaks(
+    track_list: &[TrackElement2D],
+    curve: &VerticalCurve,
+    closed: bool,
+) -> Vec<GradeBreak> {
+    let count = if closed {
+        track_list.len()
+    } else {
+        track_list.len().saturating_sub(1)
+    };
+
+    (0..count)
+        .map(|index| {
+            let pair = [
+                track_list[index],
+                track_list[(index + 1) % track_list.len()],
+            ];
+            let grade_change = pair[1].grade - pair[0].grade;
+            let wanted = match *curve {
+                VerticalCurve::KValue(k) => k * grade_change.abs(),
+                VerticalCurve::Length(length) => length,
+            };
+            let length = if grade_change == 0. {
+                0.
+            } else {
+                wanted
+                    .min(element_length(&pair[0]))
+                    .min(element_length(&pair[1]))
+                    .max(0.)
+            };
+
+            GradeBreak {
+                grade_change,
+                length,
+            }
+        })
+        .collect()
+}
+
+/// vertical profile of every element, an open track starts and ends on its tangents
+pub fn element_profiles(
+    start_transform: &Transform,
+    track_list: &[TrackElement2D],
+    curve: &VerticalCurve,
+) -> Vec<ElementProfile> {
+    let closed = is_closed(track_list);
+    let breaks = grade_breaks(track_list, curve, closed);
+    let mut start = *start_transform;
+    let centerline_height = |track: &TrackElement2D, bank_angle: f32| {
+        track
+            .bank_pivot
+            .centerline_height(track.width, bank_angle.to_radians(), track.curvature)
+    };
+
+    // height the centerline steps down by at each joint, in the same order as the grade breaks
+    let pivot_steps: Vec<f32> = (0..breaks.len())
+        .map(|index| {
+            let previous = &track_list[index];
+            let next = &track_list[(index + 1) % track_list.len()];
+            centerline_height(previous, previous.end_bank_angle)
+                - centerline_height(next, next.start_bank_angle)
+        })
+        .collect();
+
+    track_list
+        .iter()
+        .enumerate()
+        .map(|(index, track)| {
+            let previous_joint = index
+                .checked_sub(1)
+                .or_else(|| breaks.len().checked_sub(1).filter(|_| closed));
+            let profile = ElementProfile {
+                start_elevation: start.translation.z,
+                grade: track.grade,
+                length: element_length(track),
+                start: previous_joint.map_or_else(GradeBreak::default, |joint| breaks[joint]),
+                end: breaks.get(index).copied().unwrap_or_default(),
+                start_pivot_offset: previous_joint.map_or(0., |joint| pivot_steps[joint] / 2.),
+                end_pivot_offset: pivot_steps.get(index).map_or(0., |step| -step / 2.),
+            };
+            start = start * element_end_transform(track);
+            profile
+        })
+        .collect()
+}
+
+/// distance needed to stop from speed (ft/s)
+pub fn stopping_sight_distance(speed: f32) -> f32 {
+    speed * REACTION_TIME + speed * speed / (2. * BRAKING_DECELERATION)
+}
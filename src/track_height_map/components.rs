@@ -18,3 +18,8 @@ impl Material for TextureMaterial {
         "shaders/texture_shader.wgsl".into()
     }
 }
+
+// labels
+// camera rendering the track height map
+#[derive(Component)]
+pub struct HeightMapCamera;
@@ -6,11 +6,14 @@ pub mod systems;
 use components::*;
 use systems::*;
 
+use crate::track::systems::update_track;
+
 pub struct TrackHeightMapPlugin;
 
 impl Plugin for TrackHeightMapPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(MaterialPlugin::<TextureMaterial>::default())
-            .add_startup_system(setup);
+            .add_startup_system(setup)
+            .add_system(update_height_map.after(update_track));
     }
 }
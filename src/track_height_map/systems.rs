@@ -1,12 +1,14 @@
 use bevy::core_pipeline::clear_color::ClearColorConfig;
 use bevy::prelude::*;
 use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::render::primitives::Aabb;
 use bevy::render::render_resource::{
     Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
 };
 use bevy::render::view::RenderLayers;
 
 use crate::components::*;
+use crate::systems::MAX_TRACK_HEIGHT;
 use crate::track_height_map::components::*;
 
 pub fn setup(
@@ -14,43 +16,31 @@ pub fn setup(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut images: ResMut<Assets<Image>>,
-    mut global_resource: ResMut<GlobalResource>,
+    global_resource: Res<GlobalResource>,
     mut track_resource: ResMut<TrackResource>,
-    mut mesh_resource: ResMut<MeshResource>,
-    mut texture_material_asset: ResMut<Assets<TextureMaterial>>,
 ) {
     create_height_map(
         &mut commands,
         &mut meshes,
         &mut materials,
         &mut images,
-        &mut global_resource,
+        &global_resource,
         &mut track_resource,
-        &mut mesh_resource,
-        &mut texture_material_asset,
     );
 }
 
+/// spawns the height map image, its camera and the plane showing it, the track elements are
+/// added to the render to texture pass by update_height_map
 pub fn create_height_map(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
     images: &mut ResMut<Assets<Image>>,
-    global_resource: &mut ResMut<GlobalResource>,
+    global_resource: &GlobalResource,
     track_resource: &mut ResMut<TrackResource>,
-    mesh_resource: &mut ResMut<MeshResource>,
-    texture_material_asset: &mut ResMut<Assets<TextureMaterial>>,
 ) {
     // position
-    let quad_size = Vec2::new(
-        global_resource.x_max - global_resource.x_min,
-        global_resource.y_max - global_resource.y_min,
-    );
-
-    let quad_center = Vec2::new(
-        quad_size.x / 2. + global_resource.x_min,
-        quad_size.y / 2. + global_resource.y_min,
-    );
+    let (quad_size, quad_center) = height_map_extent(global_resource);
 
     // texture size
     let size = Extent3d {
@@ -78,7 +68,6 @@ pub fn create_height_map(
 
     image.resize(size);
     let image_handle = images.add(image);
-    let first_pass_layer = RenderLayers::layer(1);
 
     // camera for render to texture pass
     commands.spawn((
@@ -93,43 +82,15 @@ pub fn create_height_map(
                 target: RenderTarget::Image(image_handle.clone()),
                 ..default()
             },
-            transform: Transform::from_translation(Vec3::new(quad_center.x, quad_center.y, 10.0))
-                .looking_at(Vec3::new(quad_center.x, quad_center.y, 0.0), Vec3::Y),
-            projection: OrthographicProjection {
-                near: -15.0,
-                scaling_mode: ScalingMode::Fixed {
-                    width: quad_size.x,
-                    height: quad_size.y,
-                },
-                ..default()
-            }
-            .into(),
+            transform: height_map_camera_transform(quad_center),
+            projection: height_map_projection(quad_size).into(),
             ..default()
         },
-        first_pass_layer,
+        RenderLayers::layer(1),
+        HeightMapCamera,
         MyEntity,
     ));
 
-    let texture_material = TextureMaterial {};
-
-    for (mesh_handle, transform) in mesh_resource
-        .track_mesh_list
-        .iter()
-        .zip(mesh_resource.track_mesh_transform_list.iter())
-    {
-        commands.spawn((
-            MaterialMeshBundle {
-                mesh: mesh_handle.clone(),
-                material: texture_material_asset.add(texture_material.clone()),
-                transform: *transform,
-                ..default()
-            },
-            RenderToTexturePass,
-            first_pass_layer,
-            MyEntity,
-        ));
-    }
-
     // plane for render to texture pass
     let plane_handle = meshes.add(Mesh::from(shape::Quad {
         size: quad_size,
@@ -156,5 +117,129 @@ pub fn create_height_map(
         MyEntity,
     ));
 
-    track_resource.track_map_image_handle = image_handle.clone();
+    track_resource.track_map_image_handle = image_handle;
+}
+
+/// keeps the render to texture pass in step with the track meshes and resizes the height map
+/// camera and plane to the track extent, reusing the height map image
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_height_map(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut texture_material_asset: ResMut<Assets<TextureMaterial>>,
+    mut material_handle: Local<Option<Handle<TextureMaterial>>>,
+    mut prev_extent: Local<Option<(Vec2, Vec2)>>,
+    global_resource: Res<GlobalResource>,
+    mesh_resource: Res<MeshResource>,
+    mut pass_query: Query<(Entity, &Handle<Mesh>, &mut Transform), With<RenderToTexturePass>>,
+    mut camera_query: Query<
+        (&mut Transform, &mut Projection),
+        (With<HeightMapCamera>, Without<RenderToTexturePass>),
+    >,
+    plane_query: Query<(Entity, &Handle<Mesh>), (With<PlaneElement>, Without<RenderToTexturePass>)>,
+) {
+    if !mesh_resource.is_changed() {
+        return;
+    }
+
+    // track elements
+    let element_count = mesh_resource.track_mesh_list.len();
+    let mut spawned = vec![false; element_count];
+
+    for (entity, mesh_handle, mut transform) in pass_query.iter_mut() {
+        match mesh_resource
+            .track_mesh_list
+            .iter()
+            .position(|handle| handle == mesh_handle)
+        {
+            Some(index) => {
+                spawned[index] = true;
+                if *transform != mesh_resource.track_mesh_transform_list[index] {
+                    *transform = mesh_resource.track_mesh_transform_list[index];
+                }
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    let material_handle = material_handle
+        .get_or_insert_with(|| texture_material_asset.add(TextureMaterial {}))
+        .clone();
+
+    for index in (0..element_count).filter(|index| !spawned[*index]) {
+        commands.spawn((
+            MaterialMeshBundle {
+                mesh: mesh_resource.track_mesh_list[index].clone(),
+                material: material_handle.clone(),
+                transform: mesh_resource.track_mesh_transform_list[index],
+                ..default()
+            },
+            RenderToTexturePass,
+            RenderLayers::layer(1),
+            MyEntity,
+        ));
+    }
+
+    // camera and plane follow the track extent
+    let extent = height_map_extent(&global_resource);
+    if *prev_extent == Some(extent) {
+        return;
+    }
+    *prev_extent = Some(extent);
+
+    let (quad_size, quad_center) = extent;
+
+    for (mut transform, mut projection) in camera_query.iter_mut() {
+        *transform = height_map_camera_transform(quad_center);
+        *projection = height_map_projection(quad_size).into();
+    }
+
+    for (entity, mesh_handle) in plane_query.iter() {
+        if let Some(mesh) = meshes.get_mut(mesh_handle) {
+            *mesh = Mesh::from(shape::Quad {
+                size: quad_size,
+                ..default()
+            });
+        }
+        commands
+            .entity(entity)
+            .insert(Transform::from_xyz(quad_center.x, quad_center.y, 0.0))
+            .remove::<Aabb>();
+    }
+}
+
+/// size and center of the track extent
+fn height_map_extent(global_resource: &GlobalResource) -> (Vec2, Vec2) {
+    let quad_size = Vec2::new(
+        global_resource.x_max - global_resource.x_min,
+        global_resource.y_max - global_resource.y_min,
+    );
+
+    let quad_center = Vec2::new(
+        quad_size.x / 2. + global_resource.x_min,
+        quad_size.y / 2. + global_resource.y_min,
+    );
+
+    (quad_size, quad_center)
+}
+
+fn height_map_camera_transform(quad_center: Vec2) -> Transform {
+    // above the highest track the height map covers
+    Transform::from_translation(Vec3::new(
+        quad_center.x,
+        quad_center.y,
+        MAX_TRACK_HEIGHT + 5.,
+    ))
+    .looking_at(Vec3::new(quad_center.x, quad_center.y, 0.0), Vec3::Y)
+}
+
+fn height_map_projection(quad_size: Vec2) -> OrthographicProjection {
+    OrthographicProjection {
+        near: -15.0,
+        scaling_mode: ScalingMode::Fixed {
+            width: quad_size.x,
+            height: quad_size.y,
+        },
+        ..default()
+    }
 }
@@ -1,8 +1,15 @@
 // adapted from bevy_egui example: https://github.com/mvlabat/bevy_egui/blob/v0.20.1/examples/side_panel.rs
 
-use bevy::prelude::*;
+use bevy::{prelude::*, tasks::Task};
 
-use crate::components::TrackElement2D;
+use crate::{
+    components::TrackElement2D,
+    track::{
+        banking::BankingParams,
+        generator::{CircuitParams, GeneratorError},
+        shape::ElementInput,
+    },
+};
 
 #[derive(Default, Resource)]
 pub struct OccupiedScreenSpace {
@@ -19,4 +26,21 @@ pub struct OriginalCameraTransform(pub Transform);
 pub struct UIResource {
     pub new_track_element: TrackElement2D,
     pub new_index: usize,
+    pub bookmark_name: String,
+    /// scroll the track list to the highlighted element, set when it is picked in the scene
+    pub scroll_to_selected: bool,
+    pub circuit_params: CircuitParams,
+    pub generator_status: String,
+    /// section of the connection whose pose target is set by the next click on the ground
+    pub connection_pick: Option<usize>,
+    /// seed and task of the circuit being generated on the async compute pool
+    #[allow(clippy::type_complexity)]
+    pub circuit_task: Option<(u64, Task<Result<Vec<TrackElement2D>, GeneratorError>>)>,
+    /// form the shape of elements is entered in
+    pub element_input: ElementInput,
+    /// other end of the selected range of elements, the highlighted element is one end
+    pub range_anchor: Option<usize>,
+    /// copied elements
+    pub clipboard: Vec<TrackElement2D>,
+    pub banking_params: BankingParams,
 }
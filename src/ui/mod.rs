@@ -7,6 +7,8 @@ mod systems;
 use components::*;
 use systems::*;
 
+use crate::{selection::systems::pick_track_element, track::systems::update_track_sections};
+
 pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
@@ -15,6 +17,17 @@ impl Plugin for UIPlugin {
             .init_resource::<UIResource>()
             .add_startup_system(setup)
             .add_system(ui_system)
-            .add_system(track_list_system);
+            .add_system(track_list_system.before(update_track_sections))
+            .add_system(circuit_generation_system.before(update_track_sections))
+            .add_system(
+                connection_pick_system
+                    .after(pick_track_element)
+                    .before(update_track_sections),
+            )
+            .add_system(lap_timing_system)
+            .add_system(replay_system)
+            .add_system(history_system)
+            .add_system(profile_system)
+            .add_system(plan_view_overlay_system);
     }
 }
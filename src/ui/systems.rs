@@ -1,19 +1,42 @@
 // adapted from bevy_egui example: https://github.com/mvlabat/bevy_egui/blob/v0.20.1/examples/side_panel.rs
 
 use bevy::prelude::*;
+use bevy::tasks::AsyncComputeTaskPool;
+use bevy::window::PrimaryWindow;
 use bevy_egui::{
-    egui::{self, Ui},
+    egui::{
+        self,
+        plot::{Line, Plot, PlotPoints, VLine},
+        Ui,
+    },
     EguiContexts,
 };
+use futures_lite::future;
+use std::ops::Range;
 
 use crate::{
+    camera::components::*,
     components::*,
-    systems::despawn,
-    terrain::components::TerrainMaterial,
-    terrain::systems::spawn_terrain,
-    track::systems::spawn_track,
-    track_height_map::{components::TextureMaterial, systems::create_height_map},
+    history::components::{HistoryAction, HistoryResource},
+    profile::components::*,
+    replay::components::*,
+    spline::{components::SplineEditResource, systems::ground_position},
+    terrain::components::TerrainGenerationResource,
+    timing::components::*,
+    track::{
+        banking::required_bank_angle,
+        components::{CenterlinePoint, CenterlineResource, TrackGenerationResource},
+        compound::{CompoundElement, TurnDirection},
+        connect::{ConnectMethod, ConnectTarget, Connection},
+        generator::generate_circuit,
+        shape::*,
+        systems::{element_length, is_closed},
+        vertical::{
+            grade_breaks, stopping_sight_distance, VerticalCurve, DEFAULT_VERTICAL_CURVE_LENGTH,
+        },
+    },
     ui::components::*,
+    vehicle::components::PlayerControlled,
     GlobalResource,
 };
 
@@ -26,7 +49,7 @@ pub fn set_ui_resource(
     ui_resource: &mut ResMut<UIResource>,
     track_resource: &mut ResMut<TrackResource>,
 ) {
-    ui_resource.new_index = track_resource.track_list.len() - 1;
+    ui_resource.new_index = track_resource.track_list.len().saturating_sub(1);
 
     // default values for new track element
     ui_resource.new_track_element.curvature = 0.;
@@ -37,7 +60,23 @@ pub fn set_ui_resource(
     ui_resource.new_track_element.length = 10.;
 }
 
-pub fn ui_system(mut global_resource: ResMut<GlobalResource>, mut contexts: EguiContexts) {
+#[allow(clippy::too_many_arguments)]
+pub fn ui_system(
+    mut global_resource: ResMut<GlobalResource>,
+    mut timing_resource: ResMut<TimingResource>,
+    centerline_resource: Res<CenterlineResource>,
+    mut track_resource: ResMut<TrackResource>,
+    mut ui_resource: ResMut<UIResource>,
+    mut camera_query: Query<(
+        &mut FlythroughCamera,
+        &mut PanOrbitCamera,
+        &mut CameraTransition,
+        &mut PlanViewCamera,
+    )>,
+    mut input_map: ResMut<CameraInputMap>,
+    mut rebind_resource: ResMut<CameraRebindResource>,
+    mut contexts: EguiContexts,
+) {
     let ctx = contexts.ctx_mut();
 
     egui::SidePanel::right("right_panel")
@@ -92,63 +131,569 @@ pub fn ui_system(mut global_resource: ResMut<GlobalResource>, mut contexts: Egui
                 &mut global_resource.show_track_map,
                 "Show Track Map on Terrain",
             ));
+
+            ui.allocate_space(egui::Vec2::new(1.0, 10.0));
+
+            if let Ok((mut flythrough, mut pan_orbit, mut transition, mut plan_view)) =
+                camera_query.get_single_mut()
+            {
+                camera_menu(
+                    ui,
+                    &mut global_resource,
+                    &mut flythrough,
+                    &mut pan_orbit,
+                    &centerline_resource,
+                );
+
+                match global_resource.camera_mode {
+                    CameraMode::Orbit => framing_menu(
+                        ui,
+                        &pan_orbit,
+                        &mut transition,
+                        &mut track_resource,
+                        &mut ui_resource,
+                    ),
+                    CameraMode::PlanView => plan_view_menu(ui, &mut plan_view),
+                    CameraMode::Flythrough => {}
+                }
+            }
+
+            camera_input_menu(ui, &mut input_map, &mut rebind_resource);
+
+            ui.allocate_space(egui::Vec2::new(1.0, 10.0));
+
+            ui.heading("Play Mode");
+
+            ui.allocate_space(egui::Vec2::new(1.0, 10.0));
+
+            // switching modes keeps the generated world
+            let play_label = match global_resource.app_mode {
+                AppMode::Editor => "Drive",
+                AppMode::Play => "Back to Editor",
+            };
+            if ui
+                .add(egui::Button::new(play_label).min_size(egui::Vec2::new(100., 30.)))
+                .clicked()
+            {
+                global_resource.app_mode = match global_resource.app_mode {
+                    AppMode::Editor => AppMode::Play,
+                    AppMode::Play => AppMode::Editor,
+                };
+            }
+
+            ui.add(egui::Checkbox::new(
+                &mut global_resource.autopilot,
+                "Autopilot",
+            ));
+            ui.add(egui::Checkbox::new(
+                &mut global_resource.racing_line,
+                "Autopilot Follows Racing Line",
+            ));
+
+            if global_resource.app_mode == AppMode::Play {
+                ui.label("W / S: throttle and brake");
+                ui.label("A / D: steer");
+                ui.label("R: reset to start");
+            }
+
+            ui.allocate_space(egui::Vec2::new(1.0, 10.0));
+
+            timing_menu(ui, &mut timing_resource);
+        });
+}
+
+/// editor camera mode, orbit and flythrough controls
+fn camera_menu(
+    ui: &mut Ui,
+    global_resource: &mut ResMut<GlobalResource>,
+    flythrough: &mut FlythroughCamera,
+    pan_orbit: &mut Mut<PanOrbitCamera>,
+    centerline_resource: &CenterlineResource,
+) {
+    ui.heading("Camera");
+
+    ui.allocate_space(egui::Vec2::new(1.0, 10.0));
+
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut global_resource.camera_mode, CameraMode::Orbit, "Orbit");
+        ui.radio_value(
+            &mut global_resource.camera_mode,
+            CameraMode::Flythrough,
+            "Flythrough",
+        );
+        ui.radio_value(
+            &mut global_resource.camera_mode,
+            CameraMode::PlanView,
+            "Plan View",
+        );
+    });
+
+    if global_resource.camera_mode == CameraMode::Orbit {
+        orbit_menu(ui, pan_orbit);
+        return;
+    }
+    if global_resource.camera_mode != CameraMode::Flythrough {
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        let play_label = if flythrough.playing { "Pause" } else { "Play" };
+        if ui.button(play_label).clicked() {
+            flythrough.playing = !flythrough.playing;
+        }
+        if ui.button("Restart").clicked() {
+            flythrough.s = 0.;
+        }
+    });
+
+    ui.add(
+        egui::Slider::new(&mut flythrough.s, 0.0..=centerline_resource.length.max(0.))
+            .text("Position"),
+    );
+    ui.add(egui::Slider::new(&mut flythrough.speed, 1.0..=100.0).text("Speed"));
+    ui.add(egui::Slider::new(&mut flythrough.height, 0.5..=20.0).text("Height"));
+    ui.add(egui::Checkbox::new(
+        &mut flythrough.bank_roll,
+        "Roll With Banking",
+    ));
+}
+
+/// frame the track or the highlighted element and manage camera bookmarks
+fn framing_menu(
+    ui: &mut Ui,
+    pan_orbit: &PanOrbitCamera,
+    transition: &mut CameraTransition,
+    track_resource: &mut ResMut<TrackResource>,
+    ui_resource: &mut ResMut<UIResource>,
+) {
+    ui.horizontal(|ui| {
+        if ui.button("Frame Track").clicked() {
+            transition.command = Some(FramingCommand::FrameTrack);
+        }
+        if ui.button("Focus Selected").clicked() {
+            transition.command = Some(FramingCommand::FocusSelected);
+        }
+    });
+
+    egui::CollapsingHeader::new("Bookmarks").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut ui_resource.bookmark_name);
+            if ui.button("Add").clicked() {
+                let name = if ui_resource.bookmark_name.trim().is_empty() {
+                    format!("View {}", track_resource.camera_bookmarks.len() + 1)
+                } else {
+                    ui_resource.bookmark_name.trim().to_string()
+                };
+                track_resource.camera_bookmarks.push(CameraBookmark {
+                    name,
+                    view: CameraView::from_camera(pan_orbit),
+                });
+                ui_resource.bookmark_name.clear();
+            }
+        });
+
+        let mut removed = None;
+        for (index, bookmark) in track_resource.camera_bookmarks.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button(&bookmark.name).clicked() {
+                    transition.command = Some(FramingCommand::Bookmark(index));
+                }
+                if ui.small_button("x").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            track_resource.camera_bookmarks.remove(index);
+        }
+    });
+}
+
+/// plan view framing, the track drawing itself is done by plan_view_overlay_system
+fn plan_view_menu(ui: &mut Ui, plan_view: &mut PlanViewCamera) {
+    ui.horizontal(|ui| {
+        if ui.button("Frame Track").clicked() {
+            plan_view.command = Some(FramingCommand::FrameTrack);
+        }
+        if ui.button("Focus Selected").clicked() {
+            plan_view.command = Some(FramingCommand::FocusSelected);
+        }
+    });
+    ui.label(format!("{:.0} ft visible vertically", plan_view.height));
+}
+
+/// orbit camera options, edited on copies so the camera only moves when something changed
+fn orbit_menu(ui: &mut Ui, pan_orbit: &mut Mut<PanOrbitCamera>) {
+    let mut turntable = pan_orbit.turntable;
+    let mut up = pan_orbit.up;
+    let mut min_pitch = pan_orbit.min_pitch.to_degrees();
+    let mut max_pitch = pan_orbit.max_pitch.to_degrees();
+
+    ui.add(egui::Checkbox::new(&mut turntable, "Turntable"));
+    ui.horizontal(|ui| {
+        ui.label("Up Axis");
+        ui.radio_value(&mut up, Vec3::Z, "Z");
+        ui.radio_value(&mut up, Vec3::Y, "Y");
+    });
+    ui.add(egui::Slider::new(&mut min_pitch, -89.0..=89.0).text("Min Pitch"));
+    ui.add(egui::Slider::new(&mut max_pitch, -89.0..=89.0).text("Max Pitch"));
+    max_pitch = max_pitch.max(min_pitch);
+
+    if turntable != pan_orbit.turntable
+        || up != pan_orbit.up
+        || min_pitch != pan_orbit.min_pitch.to_degrees()
+        || max_pitch != pan_orbit.max_pitch.to_degrees()
+    {
+        pan_orbit.turntable = turntable;
+        pan_orbit.up = up;
+        pan_orbit.min_pitch = min_pitch.to_radians();
+        pan_orbit.max_pitch = max_pitch.to_radians();
+        pan_orbit.pitch = pan_orbit
+            .pitch
+            .clamp(pan_orbit.min_pitch, pan_orbit.max_pitch);
+    }
+}
+
+/// camera key, mouse and gamepad bindings, edited on a copy so the settings file is only
+/// written when something changed
+fn camera_input_menu(
+    ui: &mut Ui,
+    input_map: &mut ResMut<CameraInputMap>,
+    rebind_resource: &mut ResMut<CameraRebindResource>,
+) {
+    egui::CollapsingHeader::new("Camera Input").show(ui, |ui| {
+        let mut edited = (**input_map).clone();
+
+        egui::Grid::new("camera_bindings").show(ui, |ui| {
+            for action_binding in &edited.action_bindings {
+                ui.label(action_binding.action.label());
+                for (slot, binding) in action_binding.bindings.iter().enumerate() {
+                    let text = if rebind_resource.pending == Some((action_binding.action, slot)) {
+                        String::from("press a key...")
+                    } else {
+                        binding.to_text()
+                    };
+                    if ui.button(text).clicked() {
+                        rebind_resource.pending = Some((action_binding.action, slot));
+                    }
+                }
+                ui.end_row();
+            }
+        });
+        ui.label("Escape cancels, Delete clears a binding");
+
+        ui.add(egui::Slider::new(&mut edited.fly_speed, 0.1..=5.0).text("Fly Speed"));
+        ui.add(egui::Slider::new(&mut edited.orbit_speed, 0.1..=5.0).text("Orbit Speed"));
+        ui.add(egui::Checkbox::new(
+            &mut edited.invert_y,
+            "Invert Vertical Orbit",
+        ));
+        ui.add(egui::Checkbox::new(&mut edited.gamepad_enabled, "Gamepad"));
+
+        if ui.button("Reset to Defaults").clicked() {
+            edited = CameraInputMap::default();
+        }
+
+        if edited != **input_map {
+            **input_map = edited;
+        }
+
+        if !rebind_resource.status.is_empty() {
+            ui.label(&rebind_resource.status);
+        }
+    });
+}
+
+/// sector boundaries and checkpoint spacing, edited on a copy so lap times are only reset when
+/// something actually changed
+fn timing_menu(ui: &mut Ui, timing_resource: &mut ResMut<TimingResource>) {
+    ui.heading("Timing");
+
+    ui.allocate_space(egui::Vec2::new(1.0, 10.0));
+
+    let mut checkpoint_spacing = timing_resource.checkpoint_spacing;
+    ui.add(egui::Slider::new(&mut checkpoint_spacing, 5.0..=100.0).text("Checkpoint Spacing"));
+    if checkpoint_spacing != timing_resource.checkpoint_spacing {
+        timing_resource.checkpoint_spacing = checkpoint_spacing;
+    }
+
+    let mut sector_boundaries = timing_resource.sector_boundaries.clone();
+    let mut remove_index = None;
+
+    for (index, boundary) in sector_boundaries.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(format!("Sector {} Start", index + 2));
+
+            egui::ComboBox::from_id_source(("sector_boundary_kind", index))
+                .selected_text(match boundary.kind {
+                    SectorBoundaryKind::ElementIndex => "Element",
+                    SectorBoundaryKind::ArcLength => "Arc Length",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut boundary.kind,
+                        SectorBoundaryKind::ElementIndex,
+                        "Element",
+                    );
+                    ui.selectable_value(
+                        &mut boundary.kind,
+                        SectorBoundaryKind::ArcLength,
+                        "Arc Length",
+                    );
+                });
+
+            match boundary.kind {
+                SectorBoundaryKind::ElementIndex => {
+                    ui.add(egui::DragValue::new(&mut boundary.element_index).prefix("index:   "));
+                }
+                SectorBoundaryKind::ArcLength => {
+                    ui.add(
+                        egui::DragValue::new(&mut boundary.s)
+                            .clamp_range(0..=10000)
+                            .prefix("s:   "),
+                    );
+                }
+            }
+
+            if ui.button("Remove").clicked() {
+                remove_index = Some(index);
+            }
+        });
+    }
+
+    if let Some(index) = remove_index {
+        sector_boundaries.remove(index);
+    }
+
+    if ui.button("Add Sector Boundary").clicked() {
+        sector_boundaries.push(SectorBoundary::default());
+    }
+
+    if sector_boundaries != timing_resource.sector_boundaries {
+        timing_resource.sector_boundaries = sector_boundaries;
+    }
+}
+
+/// draws the centerline, track edges, element boundaries and element indices over the plan view
+pub fn plan_view_overlay_system(
+    global_resource: Res<GlobalResource>,
+    centerline_resource: Res<CenterlineResource>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<&PlanViewCamera>,
+    mut contexts: EguiContexts,
+) {
+    if global_resource.app_mode != AppMode::Editor
+        || global_resource.camera_mode != CameraMode::PlanView
+        || centerline_resource.points.len() < 2
+    {
+        return;
+    }
+
+    let (Ok(window), Ok(plan_view)) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+
+    // same mapping as the orthographic projection, egui y points down
+    let pixels_per_unit = window.height() / plan_view.height;
+    let window_center = egui::pos2(window.width() / 2., window.height() / 2.);
+    let to_screen = |p: Vec3| {
+        let offset = (p.truncate() - plan_view.center) * pixels_per_unit;
+        window_center + egui::vec2(offset.x, -offset.y)
+    };
+
+    let painter = contexts
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+
+    let points = &centerline_resource.points;
+    let highlighted = usize::try_from(global_resource.highlighted_track_index).ok();
+
+    let mut centerline: Vec<egui::Pos2> = points.iter().map(|p| to_screen(p.position)).collect();
+    let mut left_edge: Vec<egui::Pos2> = points
+        .iter()
+        .map(|p| to_screen(p.position + p.left() * p.width / 2.))
+        .collect();
+    let mut right_edge: Vec<egui::Pos2> = points
+        .iter()
+        .map(|p| to_screen(p.position - p.left() * p.width / 2.))
+        .collect();
+    if centerline_resource.closed {
+        centerline.push(centerline[0]);
+        left_edge.push(left_edge[0]);
+        right_edge.push(right_edge[0]);
+    }
+
+    let edge_stroke = egui::Stroke::new(1.5, egui::Color32::WHITE);
+    painter.add(egui::Shape::line(left_edge, edge_stroke));
+    painter.add(egui::Shape::line(right_edge, edge_stroke));
+    painter.add(egui::Shape::dashed_line(
+        &centerline,
+        egui::Stroke::new(1., egui::Color32::YELLOW),
+        6.,
+        4.,
+    ));
+
+    // element boundaries, the highlighted element and the element index labels
+    let mut start = 0;
+    while start < points.len() {
+        let element_index = points[start].element_index;
+        let end = points[start..]
+            .iter()
+            .position(|p| p.element_index != element_index)
+            .map_or(points.len(), |offset| start + offset);
+
+        let boundary = &points[start];
+        painter.line_segment(
+            [
+                to_screen(boundary.position + boundary.left() * boundary.width / 2.),
+                to_screen(boundary.position - boundary.left() * boundary.width / 2.),
+            ],
+            egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE),
+        );
+
+        if highlighted == Some(element_index) {
+            painter.add(egui::Shape::line(
+                points[start..end]
+                    .iter()
+                    .map(|p| to_screen(p.position))
+                    .collect(),
+                egui::Stroke::new(4., egui::Color32::from_rgb(255, 140, 0)),
+            ));
+        }
+
+        painter.text(
+            to_screen(points[(start + end) / 2].position),
+            egui::Align2::CENTER_CENTER,
+            element_index.to_string(),
+            egui::FontId::proportional(14.),
+            egui::Color32::WHITE,
+        );
+
+        start = end;
+    }
+}
+
+/// current, last and best lap of the player vehicle with sector splits
+pub fn lap_timing_system(
+    global_resource: Res<GlobalResource>,
+    timing_resource: Res<TimingResource>,
+    timer_query: Query<&LapTimer, With<PlayerControlled>>,
+    mut contexts: EguiContexts,
+) {
+    if global_resource.app_mode != AppMode::Play {
+        return;
+    }
+
+    let Ok(timer) = timer_query.get_single() else {
+        return;
+    };
+
+    let ctx = contexts.ctx_mut();
+
+    let lap_text = |time: Option<f32>, valid: bool| match time {
+        Some(time) if valid => format_lap_time(time),
+        Some(time) => format_lap_time(time) + " (invalid)",
+        None => String::from("-"),
+    };
+
+    egui::Window::new("Lap Timing")
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label(format!("Lap {}", timer.lap_count + 1));
+            ui.label(format!(
+                "Current:   {}",
+                lap_text(Some(timer.lap_time), timer.valid)
+            ));
+            ui.label(format!(
+                "Last:   {}",
+                lap_text(timer.last_lap, timer.last_lap_valid)
+            ));
+            ui.label(format!("Best:   {}", lap_text(timer.best_lap, true)));
+
+            ui.allocate_space(egui::Vec2::new(1.0, 5.0));
+
+            let split_text = |times: &[f32], sector: usize| {
+                times
+                    .get(sector)
+                    .map_or(String::from("-"), |time| format_lap_time(*time))
+            };
+
+            egui::Grid::new("sector_splits")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Sector");
+                    ui.label("Current");
+                    ui.label("Last");
+                    ui.label("Best");
+                    ui.end_row();
+
+                    for sector in 0..timing_resource.sector_count() {
+                        ui.label((sector + 1).to_string());
+                        ui.label(split_text(&timer.sector_times, sector));
+                        ui.label(split_text(&timer.last_sector_times, sector));
+                        ui.label(
+                            timer
+                                .best_sector_times
+                                .get(sector)
+                                .copied()
+                                .flatten()
+                                .map_or(String::from("-"), format_lap_time),
+                        );
+                        ui.end_row();
+                    }
+                });
         });
 }
 
 pub fn track_list_system(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
     mut global_resource: ResMut<GlobalResource>,
     mut track_resource: ResMut<TrackResource>,
-    mut mesh_resource: ResMut<MeshResource>,
     mut ui_resource: ResMut<UIResource>,
+    track_generation: Res<TrackGenerationResource>,
+    terrain_generation: Res<TerrainGenerationResource>,
+    mut spline_edit_resource: ResMut<SplineEditResource>,
     mut contexts: EguiContexts,
-    mut entity_query: Query<Entity, With<MyEntity>>,
-    mut images: ResMut<Assets<Image>>,
-    mut texture_material_asset: ResMut<Assets<TextureMaterial>>,
-    mut terrain_material_asset: ResMut<Assets<TerrainMaterial>>,
 ) {
     let ctx = contexts.ctx_mut();
 
+    // edits regenerate the track on their own, see update_track
     egui::SidePanel::left("left_panel")
         .default_width(800.0)
         .show(ctx, |ui| {
-            // updated track
-            if ui
-                .add(egui::Button::new("  Update Track").min_size(egui::Vec2::new(100., 50.)))
-                .clicked()
-            {
-                despawn(&mut commands, &mut entity_query);
-
-                spawn_track(
-                    &mut commands,
-                    &mut meshes,
-                    &mut materials,
-                    &mut global_resource,
-                    &mut track_resource,
-                    &mut mesh_resource,
-                );
+            ui.allocate_space(egui::Vec2::new(0.0, 10.0));
 
-                create_height_map(
-                    &mut commands,
-                    &mut meshes,
-                    &mut materials,
-                    &mut images,
-                    &mut global_resource,
-                    &mut track_resource,
-                    &mut mesh_resource,
-                    &mut texture_material_asset,
+            // mesh generation progress
+            if track_generation.pending {
+                ui.add(
+                    egui::ProgressBar::new(track_generation.progress()).text(format!(
+                        "Generating track {}/{}",
+                        track_generation.total - track_generation.tasks.len(),
+                        track_generation.total
+                    )),
                 );
+            }
+            if terrain_generation.task.is_some() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Generating terrain");
+                });
+            }
 
-                spawn_terrain(
-                    &mut global_resource,
-                    &mut track_resource,
-                    &mut commands,
-                    &mut meshes,
-                    &mut terrain_material_asset,
-                );
-            };
+            // how curves are entered here and in the track list
+            ui.horizontal(|ui| {
+                ui.label("Enter elements as");
+                egui::ComboBox::from_id_source("element_input")
+                    .selected_text(ui_resource.element_input.name())
+                    .show_ui(ui, |ui| {
+                        for input in ElementInput::ALL {
+                            ui.selectable_value(
+                                &mut ui_resource.element_input,
+                                input,
+                                input.name(),
+                            );
+                        }
+                    });
+            });
 
             ui.allocate_space(egui::Vec2::new(0.0, 10.0));
 
@@ -166,11 +711,12 @@ pub fn track_list_system(
                         end_bank_angle: ui_resource.new_track_element.end_bank_angle,
                         width: ui_resource.new_track_element.width,
                         length: ui_resource.new_track_element.length,
+                        grade: ui_resource.new_track_element.grade,
+                        bank_pivot: ui_resource.new_track_element.bank_pivot,
                     };
 
-                    track_resource
-                        .track_list
-                        .insert(ui_resource.new_index, track);
+                    let index = ui_resource.new_index;
+                    track_resource.insert_elements(index, &[track]);
 
                     set_ui_resource(&mut ui_resource, &mut track_resource);
                 }
@@ -187,21 +733,8 @@ pub fn track_list_system(
             // row 1
             ui.horizontal(|ui| {
                 ui.allocate_space(egui::Vec2::new(30.0, 1.0));
-                ui.add(
-                    egui::DragValue::new(&mut ui_resource.new_track_element.curvature)
-                        .clamp_range(-2..=2)
-                        .prefix("Curvature:   "),
-                );
-                ui.add(
-                    egui::DragValue::new(&mut ui_resource.new_track_element.curve_angle)
-                        .clamp_range(-190..=190)
-                        .prefix("Curve Angle:   "),
-                );
-                ui.add(
-                    egui::DragValue::new(&mut ui_resource.new_track_element.length)
-                        .clamp_range(0..=100)
-                        .prefix("Length:   "),
-                );
+                let input = ui_resource.element_input;
+                element_shape_values(ui, &mut ui_resource.new_track_element, input);
             });
 
             // row 2
@@ -217,6 +750,80 @@ pub fn track_list_system(
                         .clamp_range(-60..=60)
                         .prefix("End Bank Angle:   "),
                 );
+                ui.add(
+                    egui::DragValue::new(&mut ui_resource.new_track_element.grade)
+                        .speed(0.1)
+                        .clamp_range(-30..=30)
+                        .prefix("Grade %:   "),
+                );
+                bank_pivot_value(
+                    ui,
+                    "new_bank_pivot",
+                    &mut ui_resource.new_track_element.bank_pivot,
+                );
+            });
+
+            ui.allocate_space(egui::Vec2::new(0.0, 10.0));
+
+            // random circuit
+            egui::CollapsingHeader::new("Generate Circuit").show(ui, |ui| {
+                generator_menu(ui, &mut ui_resource);
+            });
+
+            // corners edited as one item
+            egui::CollapsingHeader::new("Compound Elements").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    for compound in CompoundElement::DEFAULTS {
+                        if ui
+                            .button(format!("Add {}", compound.name()))
+                            .on_hover_text("added at the index new elements are added at")
+                            .clicked()
+                        {
+                            let section = track_resource.insert_section(
+                                ui_resource.new_index,
+                                SectionSource::Compound(compound),
+                            );
+                            spline_edit_resource.section_inserted(section);
+                        }
+                    }
+                });
+            });
+
+            // elements reaching a target pose
+            egui::CollapsingHeader::new("Connections").show(ui, |ui| {
+                if ui
+                    .button("Add Connection")
+                    .on_hover_text(
+                        "added at the index new elements are added at, connects to the track start",
+                    )
+                    .clicked()
+                {
+                    let section = track_resource.insert_section(
+                        ui_resource.new_index,
+                        SectionSource::Connect(Connection::default()),
+                    );
+                    spline_edit_resource.section_inserted(section);
+                }
+            });
+
+            // superelevation for a design speed
+            egui::CollapsingHeader::new("Banking").show(ui, |ui| {
+                banking_menu(ui, &mut track_resource, &mut ui_resource);
+            });
+
+            // crests and sags between elements of different grade
+            egui::CollapsingHeader::new("Vertical Curves").show(ui, |ui| {
+                vertical_curve_menu(ui, &mut track_resource, &ui_resource);
+            });
+
+            // control point authoring
+            egui::CollapsingHeader::new("Spline Sections").show(ui, |ui| {
+                spline_menu(
+                    ui,
+                    &mut track_resource,
+                    &mut ui_resource,
+                    &mut spline_edit_resource,
+                );
             });
 
             ui.allocate_space(egui::Vec2::new(0.0, 10.0));
@@ -226,92 +833,1510 @@ pub fn track_list_system(
 
             ui.allocate_space(egui::Vec2::new(0.0, 10.0));
 
+            range_menu(
+                ui,
+                &mut global_resource,
+                &mut track_resource,
+                &mut ui_resource,
+            );
+
+            ui.allocate_space(egui::Vec2::new(0.0, 10.0));
+
+            let selected_range = selected_range(&global_resource, &track_resource, &ui_resource);
+
+            let element_input = ui_resource.element_input;
             egui::ScrollArea::vertical().show(ui, |ui| {
                 let mut index = 0;
 
-                while index < track_resource.track_list.len() - 1 {
-                    track_menu_item(ui, index, &mut global_resource, &mut track_resource);
+                while index < track_resource.track_list.len() {
+                    // compound elements and connections are listed as one item
+                    let end = section_menu_items(
+                        ui,
+                        index,
+                        &global_resource,
+                        &mut track_resource,
+                        &mut spline_edit_resource,
+                        &mut ui_resource.connection_pick,
+                    );
+                    if end > index {
+                        index = end;
+                        continue;
+                    }
+
+                    track_menu_item(
+                        ui,
+                        index,
+                        &mut global_resource,
+                        &mut track_resource,
+                        &mut ui_resource,
+                        selected_range.clone(),
+                        element_input,
+                    );
 
                     index += 1;
                 }
+
+                // ones after the last element, like a connection that doesn't fit yet
+                let mut starts: Vec<usize> = track_resource
+                    .sections
+                    .iter()
+                    .map(|section| section.start_index)
+                    .filter(|start| *start >= index)
+                    .collect();
+                starts.dedup();
+                for start in starts {
+                    section_menu_items(
+                        ui,
+                        start,
+                        &global_resource,
+                        &mut track_resource,
+                        &mut spline_edit_resource,
+                        &mut ui_resource.connection_pick,
+                    );
+                }
             });
+
+            // the picked element may not be in the list
+            ui_resource.scroll_to_selected = false;
         });
 }
 
-fn track_menu_item(
+/// elements from the range anchor to the highlighted element
+fn selected_range(
+    global_resource: &GlobalResource,
+    track_resource: &TrackResource,
+    ui_resource: &UIResource,
+) -> Option<Range<usize>> {
+    let highlighted = usize::try_from(global_resource.highlighted_track_index).ok()?;
+    let anchor = ui_resource.range_anchor.unwrap_or(highlighted);
+    let len = track_resource.track_list.len();
+
+    let range = highlighted.min(anchor)..(highlighted.max(anchor) + 1).min(len);
+    (!range.is_empty()).then_some(range)
+}
+
+/// selects pasted and duplicated elements
+fn select_range(
+    range: Range<usize>,
+    global_resource: &mut ResMut<GlobalResource>,
+    ui_resource: &mut ResMut<UIResource>,
+) {
+    ui_resource.range_anchor = Some(range.start);
+    global_resource.highlighted_track_index = range.end as i32 - 1;
+}
+
+/// copy, paste, duplicate, reverse and mirror over the selected range of elements
+fn range_menu(
     ui: &mut Ui,
-    index: usize,
     global_resource: &mut ResMut<GlobalResource>,
     track_resource: &mut ResMut<TrackResource>,
+    ui_resource: &mut ResMut<UIResource>,
 ) {
-    // track list
-    let index_string = index.to_string();
-    let label = String::from("Track Element ") + &index_string;
-
-    ui.allocate_space(egui::Vec2::new(30.0, 1.0));
+    let range = selected_range(global_resource, track_resource, ui_resource);
 
     ui.horizontal(|ui| {
-        if ui
-            .add(egui::SelectableLabel::new(
-                index as i32 == global_resource.highlighted_track_index,
-                &label,
-            ))
-            .clicked()
-        {
-            global_resource.highlighted_track_index = index as i32;
+        match &range {
+            Some(range) => ui.label(format!("Selected {}..{}", range.start, range.end)),
+            None => ui.label("Shift click to select a range"),
         };
 
-        if ui.button("Delete").clicked() {
-            track_resource.track_list.remove(index);
-        };
-        if ui.button("Move Up").clicked() {
-            if index > 0 {
-                track_resource.track_list.swap(index - 1, index);
+        ui.add_enabled_ui(range.is_some(), |ui| {
+            let Some(range) = range.clone() else {
+                return;
+            };
+
+            if ui.button("Copy").clicked() {
+                ui_resource.clipboard = track_resource.track_list[range.clone()].to_vec();
             }
-        }
-        if ui.button("Move Down").clicked() {
-            if index < track_resource.track_list.len() - 1 {
-                track_resource.track_list.swap(index, index + 1);
+            if ui
+                .button("Duplicate")
+                .on_hover_text("insert a copy after the selection")
+                .clicked()
+            {
+                let elements = track_resource.track_list[range.clone()].to_vec();
+                track_resource.insert_elements(range.end, &elements);
+                select_range(range.end..range.end + range.len(), global_resource, ui_resource);
             }
-        };
-    });
-
-    ui.allocate_space(egui::Vec2::new(1.0, 5.0));
-
-    // row 1
-    ui.horizontal(|ui| {
-        ui.allocate_space(egui::Vec2::new(30.0, 1.0));
-        ui.add(
-            egui::DragValue::new(&mut track_resource.track_list[index as usize].curvature)
-                .clamp_range(-2..=2)
+            if ui
+                .button("Reverse")
+                .on_hover_text("reverse the order of the selection")
+                .clicked()
+            {
+                track_resource.reverse_elements(range.clone());
+            }
+            if ui
+                .button("Mirror")
+                .on_hover_text("flip the selection to turn and bank the other way")
+                .clicked()
+            {
+                track_resource.mirror_elements(range);
+            }
+        });
+
+        if ui
+            .add_enabled(!ui_resource.clipboard.is_empty(), egui::Button::new("Paste"))
+            .on_hover_text("insert the copied elements after the selection, or at the index new elements are added at")
+            .clicked()
+        {
+            let index = range
+                .as_ref()
+                .map_or(ui_resource.new_index, |range| range.end)
+                .min(track_resource.track_list.len());
+            let elements = ui_resource.clipboard.clone();
+            track_resource.insert_elements(index, &elements);
+            select_range(index..index + elements.len(), global_resource, ui_resource);
+        }
+
+        if ui
+            .button("Mirror Layout")
+            .on_hover_text("mirror the whole track across the line it starts along")
+            .clicked()
+        {
+            track_resource.mirror_layout();
+        }
+        if ui
+            .button("Reverse Direction")
+            .on_hover_text("drive the whole track the other way round")
+            .clicked()
+        {
+            track_resource.reverse_direction();
+            ui_resource.range_anchor = None;
+            global_resource.highlighted_track_index = -1;
+        }
+    });
+}
+
+/// turns banking every curve for a design speed on and off, and compares the applied banking to
+/// the required one
+fn banking_menu(
+    ui: &mut Ui,
+    track_resource: &mut ResMut<TrackResource>,
+    ui_resource: &mut ResMut<UIResource>,
+) {
+    // the mode keeps the params it banks with, undo may have changed them
+    let mut params = track_resource.banking.unwrap_or(ui_resource.banking_params);
+
+    egui::Grid::new("banking_params").show(ui, |ui| {
+        ui.label("Design Speed (ft/s)");
+        ui.add(egui::DragValue::new(&mut params.design_speed).clamp_range(1..=400));
+        ui.end_row();
+
+        ui.label("Side Friction");
+        ui.add(
+            egui::DragValue::new(&mut params.friction)
+                .speed(0.01)
+                .clamp_range(0. ..=1.5),
+        );
+        ui.end_row();
+
+        ui.label("Max Bank Angle");
+        ui.add(egui::DragValue::new(&mut params.max_bank_angle).clamp_range(0..=60));
+        ui.end_row();
+
+        ui.label("Runout Length");
+        ui.add(egui::DragValue::new(&mut params.runout_length).clamp_range(0..=200));
+        ui.end_row();
+    });
+
+    if params != ui_resource.banking_params {
+        ui_resource.banking_params = params;
+    }
+
+    let mut auto_banking = track_resource.banking.is_some();
+    ui.checkbox(&mut auto_banking, "Auto Banking")
+        .on_hover_text(
+            "re-bank the curves whenever the track changes, sections keep their banking",
+        );
+
+    let banking = auto_banking.then_some(params);
+    if banking != track_resource.banking {
+        track_resource.banking = banking;
+    }
+
+    // required and applied banking of the curves
+    egui::ScrollArea::vertical()
+        .id_source("banking_report")
+        .max_height(200.)
+        .show(ui, |ui| {
+            egui::Grid::new("banking_report")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Element");
+                    ui.label("Radius");
+                    ui.label("Required");
+                    ui.label("Applied");
+                    ui.end_row();
+
+                    for (index, track) in track_resource.track_list.iter().enumerate() {
+                        if track.curvature == 0. {
+                            continue;
+                        }
+
+                        let required = required_bank_angle(&params, track.curvature);
+                        let applied = format!(
+                            "{:.1} to {:.1}",
+                            track.start_bank_angle, track.end_bank_angle
+                        );
+                        // runouts ramp between banks, only the constant part is held to the
+                        // required bank
+                        let runout = track.start_bank_angle != track.end_bank_angle;
+                        let matches = (track.start_bank_angle - required).abs() < 0.5;
+
+                        ui.label(index.to_string());
+                        ui.label(format!("{:.1}", 1. / track.curvature.abs()));
+                        ui.label(format!("{:.1}", required));
+                        if runout {
+                            ui.label(format!("{applied} (runout)"));
+                        } else if matches {
+                            ui.label(applied);
+                        } else {
+                            ui.colored_label(egui::Color32::from_rgb(230, 150, 50), applied);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+/// sets how long the vertical curves are, and lists them with the crests that hide a stopped
+/// car at the design speed of the banking menu
+fn vertical_curve_menu(
+    ui: &mut Ui,
+    track_resource: &mut ResMut<TrackResource>,
+    ui_resource: &ResMut<UIResource>,
+) {
+    let mut curve = track_resource.vertical_curve;
+
+    ui.horizontal(|ui| {
+        let is_length = matches!(curve, VerticalCurve::Length(_));
+        if ui.radio(!is_length, "K-Value").clicked() && is_length {
+            curve = VerticalCurve::default();
+        }
+        if ui.radio(is_length, "Length").clicked() && !is_length {
+            curve = VerticalCurve::Length(DEFAULT_VERTICAL_CURVE_LENGTH);
+        }
+
+        match &mut curve {
+            VerticalCurve::KValue(k) => {
+                ui.add(
+                    egui::DragValue::new(k)
+                        .speed(0.1)
+                        .clamp_range(0..=200)
+                        .suffix(" ft/%"),
+                );
+            }
+            VerticalCurve::Length(length) => {
+                ui.add(
+                    egui::DragValue::new(length)
+                        .clamp_range(0..=500)
+                        .suffix(" ft"),
+                );
+            }
+        }
+    });
+
+    if curve != track_resource.vertical_curve {
+        track_resource.vertical_curve = curve;
+    }
+
+    let stopping = stopping_sight_distance(ui_resource.banking_params.design_speed);
+    ui.label(format!(
+        "Stopping sight distance at {:.0} ft/s: {:.0} ft",
+        ui_resource.banking_params.design_speed, stopping
+    ));
+
+    // every grade break, curves shortened to fit their elements are shown with their length
+    egui::ScrollArea::vertical()
+        .id_source("vertical_curve_report")
+        .max_height(200.)
+        .show(ui, |ui| {
+            egui::Grid::new("vertical_curve_report")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Joint");
+                    ui.label("Type");
+                    ui.label("A %");
+                    ui.label("Length");
+                    ui.label("K");
+                    ui.label("Sight");
+                    ui.end_row();
+
+                    let track_list = &track_resource.track_list;
+                    let breaks = grade_breaks(track_list, &curve, is_closed(track_list));
+                    for (index, grade_break) in breaks.iter().enumerate() {
+                        if grade_break.grade_change == 0. {
+                            continue;
+                        }
+
+                        let crest = grade_break.grade_change < 0.;
+                        let sight = grade_break.sight_distance();
+
+                        ui.label(format!("{} / {}", index, (index + 1) % track_list.len()));
+                        ui.label(if crest { "Crest" } else { "Sag" });
+                        ui.label(format!("{:.1}", grade_break.grade_change));
+                        ui.label(format!("{:.1}", grade_break.length));
+                        ui.label(format!(
+                            "{:.1}",
+                            grade_break.length / grade_break.grade_change.abs()
+                        ));
+                        if !crest {
+                            ui.label("-");
+                        } else if sight < stopping {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(230, 150, 50),
+                                format!("{:.0}", sight),
+                            )
+                            .on_hover_text("the crest hides a stopped car at the design speed");
+                        } else {
+                            ui.label(format!("{:.0}", sight));
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+/// replaces the track list with a random closed circuit once its generation is done
+pub fn circuit_generation_system(
+    mut global_resource: ResMut<GlobalResource>,
+    mut track_resource: ResMut<TrackResource>,
+    mut ui_resource: ResMut<UIResource>,
+) {
+    if ui_resource.circuit_task.is_none() {
+        return;
+    }
+
+    let Some((seed, task)) = &mut ui_resource.circuit_task else {
+        return;
+    };
+    let Some(result) = future::block_on(future::poll_once(task)) else {
+        return;
+    };
+    let seed = *seed;
+    ui_resource.circuit_task = None;
+
+    match result {
+        Ok(track_list) => {
+            ui_resource.generator_status =
+                format!("generated {} elements from seed {}", track_list.len(), seed);
+            track_resource.track_list = track_list;
+            track_resource.sections.clear();
+            track_resource.start_transform = TrackResource::default().start_transform;
+            global_resource.highlighted_track_index = -1;
+            set_ui_resource(&mut ui_resource, &mut track_resource);
+        }
+        Err(error) => ui_resource.generator_status = error.to_string(),
+    }
+}
+
+/// moves the pose target of the picked connection to where the ground is clicked, the heading
+/// is kept
+#[allow(clippy::too_many_arguments)]
+pub fn connection_pick_system(
+    input_mouse: Res<Input<MouseButton>>,
+    input_keys: Res<Input<KeyCode>>,
+    input_map: Res<CameraInputMap>,
+    global_resource: Res<GlobalResource>,
+    mut track_resource: ResMut<TrackResource>,
+    mut ui_resource: ResMut<UIResource>,
+    mut contexts: EguiContexts,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+) {
+    let Some(section) = ui_resource.connection_pick else {
+        return;
+    };
+    let picked = track_resource
+        .sections
+        .get(section)
+        .and_then(|section| match section.source {
+            SectionSource::Connect(connection) => Some(connection),
+            _ => None,
+        })
+        .filter(|connection| matches!(connection.target, ConnectTarget::Pose { .. }));
+    let Some(mut connection) = picked.filter(|_| global_resource.app_mode == AppMode::Editor)
+    else {
+        ui_resource.connection_pick = None;
+        return;
+    };
+
+    if !input_mouse.just_pressed(MouseButton::Left)
+        || contexts.ctx_mut().is_pointer_over_area()
+        || input_map.pressed(CameraAction::Orbit, &input_keys, &input_mouse)
+        || input_map.pressed(CameraAction::Pan, &input_keys, &input_mouse)
+    {
+        return;
+    }
+
+    let (Ok(window), Ok((camera, camera_transform))) =
+        (window_query.get_single(), camera_query.get_single())
+    else {
+        return;
+    };
+    let Some(position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+        .and_then(|ray| ground_position(&ray))
+    else {
+        return;
+    };
+
+    if let ConnectTarget::Pose { heading, .. } = connection.target {
+        connection.target = ConnectTarget::Pose { position, heading };
+    }
+
+    // update_track_sections refits the elements
+    track_resource.sections[section].source = SectionSource::Connect(connection);
+    ui_resource.connection_pick = None;
+}
+
+/// parameters of a random closed circuit, generated on the async compute pool
+fn generator_menu(ui: &mut Ui, ui_resource: &mut ResMut<UIResource>) {
+    let mut params = ui_resource.circuit_params;
+
+    egui::Grid::new("generator_params").show(ui, |ui| {
+        ui.label("Seed");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut params.seed));
+            if ui.button("Random").clicked() {
+                params.seed = fastrand::u64(..);
+            }
+        });
+        ui.end_row();
+
+        ui.label("Target Length");
+        ui.add(egui::DragValue::new(&mut params.target_length).clamp_range(50..=5000));
+        ui.end_row();
+
+        ui.label("Corners");
+        ui.add(egui::DragValue::new(&mut params.corners).clamp_range(3..=30));
+        ui.end_row();
+
+        ui.label("Min Radius");
+        ui.add(egui::DragValue::new(&mut params.min_radius).clamp_range(1..=100));
+        ui.end_row();
+
+        ui.label("Max Bank Angle");
+        ui.add(egui::DragValue::new(&mut params.max_bank_angle).clamp_range(0..=60));
+        ui.end_row();
+
+        ui.label("Area");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut params.area.x).clamp_range(20..=2000));
+            ui.add(egui::DragValue::new(&mut params.area.y).clamp_range(20..=2000));
+        });
+        ui.end_row();
+
+        ui.label("Width");
+        ui.add(egui::DragValue::new(&mut params.width).clamp_range(2..=40));
+        ui.end_row();
+    });
+
+    if params != ui_resource.circuit_params {
+        ui_resource.circuit_params = params;
+    }
+
+    // the attempts can take a while on long tracks, circuit_generation_system swaps the result in
+    let generating = ui_resource.circuit_task.is_some();
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(!generating, egui::Button::new("Generate"))
+            .clicked()
+        {
+            let task = AsyncComputeTaskPool::get().spawn(async move { generate_circuit(&params) });
+            ui_resource.circuit_task = Some((params.seed, task));
+        }
+        if generating {
+            ui.spinner();
+            ui.label("Generating circuit");
+        }
+    });
+
+    if !ui_resource.generator_status.is_empty() {
+        ui.label(&ui_resource.generator_status);
+    }
+}
+
+/// spline sections and the control points of the edited one
+fn spline_menu(
+    ui: &mut Ui,
+    track_resource: &mut ResMut<TrackResource>,
+    ui_resource: &mut ResMut<UIResource>,
+    spline_edit_resource: &mut ResMut<SplineEditResource>,
+) {
+    if ui
+        .button("New Spline Section")
+        .on_hover_text("starts at the index new elements are added at")
+        .clicked()
+    {
+        // a section can't start inside another one
+        let section =
+            track_resource.insert_section(ui_resource.new_index, SectionSource::Spline(vec![]));
+        spline_edit_resource.section_inserted(section);
+        spline_edit_resource.section = Some(section);
+    }
+
+    let mut removed = None;
+
+    for (index, section) in track_resource.sections.iter().enumerate() {
+        let Some(control_points) = section.control_points() else {
+            continue;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Spline {}: elements {}..{}, {} points",
+                index,
+                section.start_index,
+                section.elements().end,
+                control_points.len()
+            ));
+
+            let editing = spline_edit_resource.section == Some(index);
+            if ui.button(if editing { "Done" } else { "Edit" }).clicked() {
+                spline_edit_resource.section = if editing { None } else { Some(index) };
+            }
+            if ui
+                .button("Convert")
+                .on_hover_text("keep the elements as plain elements")
+                .clicked()
+            {
+                removed = Some((index, false));
+            }
+            if ui.button("Delete").clicked() {
+                removed = Some((index, true));
+            }
+        });
+    }
+
+    if let Some((index, delete_elements)) = removed {
+        track_resource.remove_section(index, delete_elements);
+        spline_edit_resource.section_removed(index);
+    }
+
+    let Some(editing) = spline_edit_resource.section else {
+        return;
+    };
+    let Some(mut control_points) = track_resource
+        .sections
+        .get(editing)
+        .and_then(|section| section.control_points().cloned())
+    else {
+        return;
+    };
+
+    ui.label("Click the ground to add a control point, drag one to move it");
+
+    let mut removed_point = None;
+
+    egui::Grid::new("control_points")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Point");
+            ui.label("X");
+            ui.label("Y");
+            ui.label("Width");
+            ui.label("Bank Angle");
+            ui.label("Elevation");
+            ui.end_row();
+
+            for (index, control_point) in control_points.iter_mut().enumerate() {
+                ui.label(index.to_string());
+                ui.add(egui::DragValue::new(&mut control_point.position.x));
+                ui.add(egui::DragValue::new(&mut control_point.position.y));
+                optional_value(ui, &mut control_point.width, 8., 2. ..=40.);
+                optional_value(ui, &mut control_point.bank_angle, 0., -60. ..=60.);
+                optional_value(ui, &mut control_point.elevation, 0., -100. ..=100.);
+                if ui.button("Remove").clicked() {
+                    removed_point = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+
+    if let Some(index) = removed_point {
+        control_points.remove(index);
+    }
+
+    // update_track_sections refits the elements
+    if track_resource.sections[editing].control_points() != Some(&control_points) {
+        if let Some(section) = track_resource.sections[editing].control_points_mut() {
+            *section = control_points;
+        }
+    }
+}
+
+/// compound elements and connections starting at index, listed as one item each. Returns the
+/// end of the elements they cover.
+fn section_menu_items(
+    ui: &mut Ui,
+    index: usize,
+    global_resource: &GlobalResource,
+    track_resource: &mut ResMut<TrackResource>,
+    spline_edit_resource: &mut ResMut<SplineEditResource>,
+    connection_pick: &mut Option<usize>,
+) -> usize {
+    let mut end = index;
+    let mut section = 0;
+
+    while section < track_resource.sections.len() {
+        let item = &track_resource.sections[section];
+        if item.start_index != index {
+            section += 1;
+            continue;
+        }
+
+        let removed = match item.source {
+            SectionSource::Spline(_) => None,
+            SectionSource::Compound(_) => compound_menu_item(ui, section, track_resource),
+            SectionSource::Connect(_) => connection_menu_item(
+                ui,
+                section,
+                global_resource,
+                track_resource,
+                connection_pick,
+            ),
+        };
+
+        // a click on the ground would also add a control point
+        if *connection_pick == Some(section) {
+            spline_edit_resource.section = None;
+        }
+
+        // the sections after it move down
+        if let Some(remove_elements) = removed {
+            track_resource.remove_section(section, remove_elements);
+            spline_edit_resource.section_removed(section);
+            *connection_pick = None;
+            continue;
+        }
+
+        if track_resource.sections[section].control_points().is_none() {
+            end = end.max(track_resource.sections[section].elements().end);
+        }
+        section += 1;
+    }
+
+    end
+}
+
+/// target and method of a connection, its elements are refitted from them. Returns whether the
+/// section is to be removed with or without its elements.
+fn connection_menu_item(
+    ui: &mut Ui,
+    section: usize,
+    global_resource: &GlobalResource,
+    track_resource: &mut ResMut<TrackResource>,
+    connection_pick: &mut Option<usize>,
+) -> Option<bool> {
+    let SectionSource::Connect(mut connection) = track_resource.sections[section].source else {
+        return None;
+    };
+    let elements = track_resource.sections[section].elements();
+    let mut removed = None;
+
+    ui.allocate_space(egui::Vec2::new(30.0, 1.0));
+
+    ui.horizontal(|ui| {
+        if elements.is_empty() {
+            ui.label(format!("Connection at {} (no fit)", elements.start));
+        } else {
+            ui.label(format!(
+                "Connection (elements {}..{})",
+                elements.start, elements.end
+            ));
+        }
+
+        if ui.button("Delete").clicked() {
+            removed = Some(true);
+        }
+        if ui
+            .button("Convert")
+            .on_hover_text("keep the elements as plain elements")
+            .clicked()
+        {
+            removed = Some(false);
+        }
+    });
+
+    ui.allocate_space(egui::Vec2::new(1.0, 5.0));
+
+    // row 1, target
+    ui.horizontal(|ui| {
+        ui.allocate_space(egui::Vec2::new(30.0, 1.0));
+
+        let is_pose = matches!(connection.target, ConnectTarget::Pose { .. });
+        if ui.radio(!is_pose, "Element Start").clicked() && is_pose {
+            connection.target = ConnectTarget::ElementStart(0);
+        }
+        if ui.radio(is_pose, "Pose").clicked() && !is_pose {
+            // starts from where the element target is
+            let (position, heading) = connection
+                .target_pose(
+                    &track_resource.start_transform,
+                    &track_resource.track_list,
+                    elements.start,
+                )
+                .unwrap_or_default();
+            connection.target = ConnectTarget::Pose {
+                position,
+                heading: heading.to_degrees(),
+            };
+        }
+
+        match &mut connection.target {
+            ConnectTarget::ElementStart(index) => {
+                ui.add(
+                    egui::DragValue::new(index)
+                        .clamp_range(0..=elements.start.saturating_sub(1))
+                        .prefix("Element:   "),
+                );
+
+                let highlighted = global_resource.highlighted_track_index;
+                if ui
+                    .add_enabled(
+                        highlighted >= 0 && (highlighted as usize) < elements.start,
+                        egui::Button::new("Use Selected"),
+                    )
+                    .on_hover_text("connect to the start of the selected element")
+                    .clicked()
+                {
+                    *index = highlighted as usize;
+                }
+            }
+            ConnectTarget::Pose { position, heading } => {
+                ui.add(egui::DragValue::new(&mut position.x).prefix("X:   "));
+                ui.add(egui::DragValue::new(&mut position.y).prefix("Y:   "));
+                ui.add(
+                    egui::DragValue::new(heading)
+                        .clamp_range(-180..=180)
+                        .prefix("Heading:   "),
+                );
+
+                let picking = *connection_pick == Some(section);
+                if ui
+                    .selectable_label(picking, "Pick")
+                    .on_hover_text("click on the ground to place the target")
+                    .clicked()
+                {
+                    *connection_pick = if picking { None } else { Some(section) };
+                }
+            }
+        }
+    });
+
+    let is_pose = matches!(connection.target, ConnectTarget::Pose { .. });
+    if !is_pose && *connection_pick == Some(section) {
+        *connection_pick = None;
+    }
+
+    // row 2, method
+    ui.horizontal(|ui| {
+        ui.allocate_space(egui::Vec2::new(30.0, 1.0));
+
+        let radius = match connection.method {
+            ConnectMethod::Biarc => DEFAULT_RADIUS,
+            ConnectMethod::ArcLineArc { radius } => radius,
+        };
+        ui.radio_value(&mut connection.method, ConnectMethod::Biarc, "Biarc");
+        if ui
+            .radio(
+                matches!(connection.method, ConnectMethod::ArcLineArc { .. }),
+                "Arc-Line-Arc",
+            )
+            .clicked()
+        {
+            connection.method = ConnectMethod::ArcLineArc { radius };
+        }
+
+        if let ConnectMethod::ArcLineArc { radius } = &mut connection.method {
+            ui.add(
+                egui::DragValue::new(radius)
+                    .clamp_range(MIN_RADIUS..=1000.)
+                    .prefix("Radius:   "),
+            );
+        }
+    });
+
+    ui.allocate_space(egui::Vec2::new(1.0, 10.0));
+
+    // update_track_sections refits the elements
+    if SectionSource::Connect(connection) != track_resource.sections[section].source {
+        track_resource.sections[section].source = SectionSource::Connect(connection);
+    }
+
+    removed
+}
+
+/// parameters of a compound element, its elements are regenerated from them. Returns whether
+/// the section is to be removed with or without its elements.
+fn compound_menu_item(
+    ui: &mut Ui,
+    section: usize,
+    track_resource: &mut ResMut<TrackResource>,
+) -> Option<bool> {
+    let SectionSource::Compound(mut compound) = track_resource.sections[section].source else {
+        return None;
+    };
+    let elements = track_resource.sections[section].elements();
+    let mut removed = None;
+
+    ui.allocate_space(egui::Vec2::new(30.0, 1.0));
+
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{} (elements {}..{})",
+            compound.name(),
+            elements.start,
+            elements.end
+        ));
+
+        if ui.button("Delete").clicked() {
+            removed = Some(true);
+        }
+        if ui
+            .button("Convert")
+            .on_hover_text("keep the elements as plain elements")
+            .clicked()
+        {
+            removed = Some(false);
+        }
+    });
+
+    ui.allocate_space(egui::Vec2::new(1.0, 5.0));
+
+    ui.horizontal(|ui| {
+        ui.allocate_space(egui::Vec2::new(30.0, 1.0));
+        match &mut compound {
+            CompoundElement::Hairpin {
+                radius,
+                angle,
+                spiral_length,
+                bank_angle,
+                direction,
+            } => {
+                ui.add(
+                    egui::DragValue::new(radius)
+                        .clamp_range(2..=100)
+                        .prefix("Radius:   "),
+                );
+                ui.add(
+                    egui::DragValue::new(angle)
+                        .clamp_range(10..=270)
+                        .prefix("Angle:   "),
+                );
+                ui.add(
+                    egui::DragValue::new(spiral_length)
+                        .clamp_range(0..=100)
+                        .prefix("Spiral Length:   "),
+                );
+                ui.add(
+                    egui::DragValue::new(bank_angle)
+                        .clamp_range(-60..=60)
+                        .prefix("Bank Angle:   "),
+                );
+                direction_value(ui, direction);
+            }
+            CompoundElement::SBend {
+                offset,
+                length,
+                radius,
+                bank_angle,
+                direction,
+            }
+            | CompoundElement::Chicane {
+                offset,
+                length,
+                radius,
+                bank_angle,
+                direction,
+            } => {
+                ui.add(
+                    egui::DragValue::new(offset)
+                        .clamp_range(0..=100)
+                        .prefix("Offset:   "),
+                );
+                ui.add(
+                    egui::DragValue::new(length)
+                        .clamp_range(1..=300)
+                        .prefix("Length:   "),
+                );
+                ui.add(
+                    egui::DragValue::new(radius)
+                        .clamp_range(2..=200)
+                        .prefix("Radius:   "),
+                );
+                ui.add(
+                    egui::DragValue::new(bank_angle)
+                        .clamp_range(-60..=60)
+                        .prefix("Bank Angle:   "),
+                );
+                direction_value(ui, direction);
+            }
+            CompoundElement::Esses {
+                count,
+                angle,
+                radius,
+                bank_angle,
+                direction,
+            } => {
+                ui.add(
+                    egui::DragValue::new(count)
+                        .clamp_range(1..=10)
+                        .prefix("Count:   "),
+                );
+                ui.add(
+                    egui::DragValue::new(angle)
+                        .clamp_range(5..=120)
+                        .prefix("Angle:   "),
+                );
+                ui.add(
+                    egui::DragValue::new(radius)
+                        .clamp_range(2..=200)
+                        .prefix("Radius:   "),
+                );
+                ui.add(
+                    egui::DragValue::new(bank_angle)
+                        .clamp_range(-60..=60)
+                        .prefix("Bank Angle:   "),
+                );
+                direction_value(ui, direction);
+            }
+        }
+    });
+
+    ui.allocate_space(egui::Vec2::new(1.0, 10.0));
+
+    // update_track_sections regenerates the elements
+    if SectionSource::Compound(compound) != track_resource.sections[section].source {
+        track_resource.sections[section].source = SectionSource::Compound(compound);
+    }
+
+    removed
+}
+
+/// left or right turn of a compound element
+fn direction_value(ui: &mut Ui, direction: &mut TurnDirection) {
+    ui.radio_value(direction, TurnDirection::Left, "Left");
+    ui.radio_value(direction, TurnDirection::Right, "Right");
+}
+
+/// line the element is banked about
+fn bank_pivot_value(ui: &mut Ui, id_source: impl std::hash::Hash, bank_pivot: &mut BankPivot) {
+    ui.label("Bank Pivot:");
+    egui::ComboBox::from_id_source(id_source)
+        .selected_text(bank_pivot.name())
+        .show_ui(ui, |ui| {
+            for pivot in BankPivot::ALL {
+                ui.selectable_value(bank_pivot, pivot, pivot.name());
+            }
+        });
+}
+
+/// shape of an element in the chosen input form, converted to curvature, curve angle and length
+fn element_shape_values(ui: &mut Ui, track: &mut TrackElement2D, input: ElementInput) {
+    if input == ElementInput::Curvature {
+        ui.add(
+            egui::DragValue::new(&mut track.curvature)
+                .clamp_range(-2..=2)
                 .prefix("Curvature:   "),
         );
         ui.add(
-            egui::DragValue::new(&mut track_resource.track_list[index as usize].curve_angle)
+            egui::DragValue::new(&mut track.curve_angle)
                 .clamp_range(-190..=190)
                 .prefix("Curve Angle:   "),
         );
         ui.add(
-            egui::DragValue::new(&mut track_resource.track_list[index as usize].length)
+            egui::DragValue::new(&mut track.length)
                 .clamp_range(0..=100)
                 .prefix("Length:   "),
         );
-    });
+        return;
+    }
 
-    // row 2
-    ui.horizontal(|ui| {
-        ui.allocate_space(egui::Vec2::new(30.0, 1.0));
-        ui.add(
-            egui::DragValue::new(&mut track_resource.track_list[index as usize].start_bank_angle)
-                .clamp_range(-60..=60)
-                .prefix("Start Bank Angle:   "),
+    if input == ElementInput::EndPoint {
+        let mut point = end_point(track);
+        let forward = ui.add(
+            egui::DragValue::new(&mut point.x)
+                .speed(0.1)
+                .prefix("Forward:   "),
         );
-        ui.add(
-            egui::DragValue::new(&mut track_resource.track_list[index as usize].end_bank_angle)
-                .clamp_range(-60..=60)
-                .prefix("End Bank Angle:   "),
+        let left = ui.add(
+            egui::DragValue::new(&mut point.y)
+                .speed(0.1)
+                .prefix("Left:   "),
         );
+        ui.label(format!("Heading Change: {:.1}", heading_change(track)));
+
+        if forward.changed() || left.changed() {
+            set_end_point(track, point);
+        }
+        return;
+    }
+
+    // a straight switched to a curve keeps its length
+    let mut direction = turn_direction(track);
+    let mut radius = if direction.is_some() {
+        radius(track)
+    } else {
+        DEFAULT_RADIUS
+    };
+    let mut arc_length = element_length(track);
+    let mut angle = (arc_length / radius).to_degrees();
+
+    let mut changed = false;
+    for (value, label) in [
+        (None, "Straight"),
+        (Some(TurnDirection::Left), "Left"),
+        (Some(TurnDirection::Right), "Right"),
+    ] {
+        changed |= ui.radio_value(&mut direction, value, label).changed();
+    }
+
+    if direction.is_none() {
+        if ui
+            .add(
+                egui::DragValue::new(&mut arc_length)
+                    .clamp_range(0..=100)
+                    .prefix("Length:   "),
+            )
+            .changed()
+            || changed
+        {
+            set_straight(track, arc_length);
+        }
+        return;
+    }
+
+    changed |= ui
+        .add(
+            egui::DragValue::new(&mut radius)
+                .clamp_range(MIN_RADIUS..=1000.)
+                .prefix("Radius:   "),
+        )
+        .changed();
+
+    if input == ElementInput::RadiusAngle {
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut angle)
+                    .clamp_range(0..=360)
+                    .prefix("Angle:   "),
+            )
+            .changed();
+        if changed {
+            set_radius_angle(track, direction, radius, angle);
+        }
+    } else {
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut arc_length)
+                    .clamp_range(0..=1000)
+                    .prefix("Arc Length:   "),
+            )
+            .changed();
+        if changed {
+            set_radius_length(track, direction, radius, arc_length);
+        }
+    }
+}
+
+/// checkbox setting a value, with a drag value for it once set
+fn optional_value(
+    ui: &mut Ui,
+    value: &mut Option<f32>,
+    default: f32,
+    range: std::ops::RangeInclusive<f32>,
+) {
+    ui.horizontal(|ui| {
+        let mut set = value.is_some();
+        if ui.checkbox(&mut set, "").changed() {
+            *value = set.then_some(default);
+        }
+        if let Some(value) = value {
+            ui.add(egui::DragValue::new(value).clamp_range(range));
+        }
+    });
+}
+
+fn track_menu_item(
+    ui: &mut Ui,
+    index: usize,
+    global_resource: &mut ResMut<GlobalResource>,
+    track_resource: &mut ResMut<TrackResource>,
+    ui_resource: &mut ResMut<UIResource>,
+    selected_range: Option<Range<usize>>,
+    element_input: ElementInput,
+) {
+    // track list
+    let index_string = index.to_string();
+    let section = track_resource.section_of(index);
+    let label = match section {
+        Some(section) => format!("Track Element {index_string} (section {section})"),
+        None => String::from("Track Element ") + &index_string,
+    };
+
+    ui.allocate_space(egui::Vec2::new(30.0, 1.0));
+
+    ui.horizontal(|ui| {
+        let selected = selected_range.is_some_and(|range| range.contains(&index));
+        let response = ui.add(egui::SelectableLabel::new(selected, &label));
+        if response.clicked() {
+            // shift click extends the selection from the highlighted element
+            let highlighted = global_resource.highlighted_track_index;
+            ui_resource.range_anchor = if ui.input(|input| input.modifiers.shift) {
+                ui_resource
+                    .range_anchor
+                    .or_else(|| usize::try_from(highlighted).ok())
+            } else {
+                None
+            };
+            global_resource.highlighted_track_index = index as i32;
+        };
+
+        // element picked in the scene
+        if index as i32 == global_resource.highlighted_track_index && ui_resource.scroll_to_selected
+        {
+            response.scroll_to_me(Some(egui::Align::Center));
+            ui_resource.scroll_to_selected = false;
+        }
+
+        if ui.button("Delete").clicked() {
+            track_resource.remove_elements(index..index + 1);
+        };
+        if ui.button("Move Up").clicked() {
+            if index > 0 {
+                track_resource.swap_elements(index - 1, index);
+            }
+        }
+        if ui.button("Move Down").clicked() {
+            if index + 1 < track_resource.track_list.len() {
+                track_resource.swap_elements(index, index + 1);
+            }
+        };
     });
 
+    ui.allocate_space(egui::Vec2::new(1.0, 5.0));
+
+    // the values are edited on a copy, the track only changes when they do
+    let Some(mut track) = track_resource.track_list.get(index).copied() else {
+        return;
+    };
+
+    // section elements are generated from their section
+    ui.add_enabled_ui(section.is_none(), |ui| {
+        // row 1
+        ui.horizontal(|ui| {
+            ui.allocate_space(egui::Vec2::new(30.0, 1.0));
+            element_shape_values(ui, &mut track, element_input);
+        });
+
+        // row 2
+        ui.horizontal(|ui| {
+            ui.allocate_space(egui::Vec2::new(30.0, 1.0));
+            ui.add(
+                egui::DragValue::new(&mut track.start_bank_angle)
+                    .clamp_range(-60..=60)
+                    .prefix("Start Bank Angle:   "),
+            );
+            ui.add(
+                egui::DragValue::new(&mut track.end_bank_angle)
+                    .clamp_range(-60..=60)
+                    .prefix("End Bank Angle:   "),
+            );
+            ui.add(
+                egui::DragValue::new(&mut track.grade)
+                    .speed(0.1)
+                    .clamp_range(-30..=30)
+                    .prefix("Grade %:   "),
+            );
+            bank_pivot_value(ui, ("bank_pivot", index), &mut track.bank_pivot);
+        });
+    });
+
+    if track_resource.track_list[index] != track {
+        track_resource.track_list[index] = track;
+    }
+
     ui.allocate_space(egui::Vec2::new(1.0, 10.0));
 }
+
+/// recording, saving and loading replays and ghost playback controls
+pub fn replay_system(
+    global_resource: Res<GlobalResource>,
+    centerline_resource: Res<CenterlineResource>,
+    mut replay_resource: ResMut<ReplayResource>,
+    mut contexts: EguiContexts,
+) {
+    if global_resource.app_mode != AppMode::Play {
+        return;
+    }
+
+    let ctx = contexts.ctx_mut();
+
+    egui::Window::new("Replay")
+        .resizable(false)
+        .show(ctx, |ui| {
+            // recording
+            let record_label = if replay_resource.recording {
+                "Stop Recording"
+            } else {
+                "Record"
+            };
+            if ui.button(record_label).clicked() {
+                replay_resource.recording = !replay_resource.recording;
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("File:");
+                ui.text_edit_singleline(&mut replay_resource.file_path);
+            });
+
+            ui.horizontal(|ui| {
+                let has_recording = replay_resource.last_recording.is_some();
+
+                if ui
+                    .add_enabled(has_recording, egui::Button::new("Save Recording"))
+                    .clicked()
+                {
+                    if let Some(recording) = &replay_resource.last_recording {
+                        replay_resource.status = match recording.save(&replay_resource.file_path) {
+                            Ok(()) => format!("saved {}", replay_resource.file_path),
+                            Err(error) => format!("save failed: {error}"),
+                        };
+                    }
+                }
+
+                if ui
+                    .add_enabled(has_recording, egui::Button::new("Recording as Ghost"))
+                    .clicked()
+                {
+                    replay_resource.ghost = replay_resource.last_recording.clone();
+                    replay_resource.time = 0.;
+                }
+
+                if ui.button("Load Ghost").clicked() {
+                    match Replay::load(&replay_resource.file_path, centerline_resource.layout_hash)
+                    {
+                        Ok(replay) => {
+                            replay_resource.status =
+                                format!("loaded {}", replay_resource.file_path);
+                            replay_resource.ghost = Some(replay);
+                            replay_resource.time = 0.;
+                        }
+                        Err(error) => {
+                            replay_resource.status = format!("load failed: {error}");
+                        }
+                    }
+                }
+            });
+
+            // ghost playback
+            if let Some(duration) = replay_resource.ghost.as_ref().map(|ghost| ghost.duration()) {
+                ui.allocate_space(egui::Vec2::new(1.0, 5.0));
+
+                ui.horizontal(|ui| {
+                    let play_label = if replay_resource.playing {
+                        "Pause"
+                    } else {
+                        "Play"
+                    };
+                    if ui.button(play_label).clicked() {
+                        replay_resource.playing = !replay_resource.playing;
+                    }
+                    if ui.button("Restart").clicked() {
+                        replay_resource.time = 0.;
+                    }
+                    if ui.button("Remove Ghost").clicked() {
+                        replay_resource.ghost = None;
+                    }
+                });
+
+                ui.add(
+                    egui::Slider::new(&mut replay_resource.time, 0.0..=duration)
+                        .text("Time")
+                        .custom_formatter(|time, _| format_lap_time(time as f32)),
+                );
+                ui.add(
+                    egui::Slider::new(&mut replay_resource.speed, 0.1..=4.0)
+                        .logarithmic(true)
+                        .text("Speed"),
+                );
+            }
+
+            if !replay_resource.status.is_empty() {
+                ui.label(&replay_resource.status);
+            }
+        });
+}
+
+/// undo and redo buttons and the list of edits, clicking an edit returns to the state after it
+pub fn history_system(
+    global_resource: Res<GlobalResource>,
+    mut history_resource: ResMut<HistoryResource>,
+    mut contexts: EguiContexts,
+) {
+    if global_resource.app_mode != AppMode::Editor {
+        return;
+    }
+
+    let ctx = contexts.ctx_mut();
+
+    let mut action = None;
+
+    egui::Window::new("History")
+        .default_open(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        !history_resource.undo_stack.is_empty(),
+                        egui::Button::new("Undo"),
+                    )
+                    .on_hover_text("Ctrl+Z")
+                    .clicked()
+                {
+                    action = Some(HistoryAction::Undo);
+                }
+                if ui
+                    .add_enabled(
+                        !history_resource.redo_stack.is_empty(),
+                        egui::Button::new("Redo"),
+                    )
+                    .on_hover_text("Ctrl+Shift+Z")
+                    .clicked()
+                {
+                    action = Some(HistoryAction::Redo);
+                }
+            });
+
+            ui.allocate_space(egui::Vec2::new(1.0, 5.0));
+
+            egui::ScrollArea::vertical()
+                .max_height(300.)
+                .show(ui, |ui| {
+                    let applied = history_resource.undo_stack.len();
+
+                    if ui
+                        .add(egui::SelectableLabel::new(applied == 0, "Start"))
+                        .clicked()
+                    {
+                        action = Some(HistoryAction::JumpTo(0));
+                    }
+
+                    for (index, entry) in history_resource.undo_stack.iter().enumerate() {
+                        if ui
+                            .add(egui::SelectableLabel::new(
+                                index + 1 == applied,
+                                &entry.label,
+                            ))
+                            .clicked()
+                        {
+                            action = Some(HistoryAction::JumpTo(index + 1));
+                        }
+                    }
+
+                    // undone edits, the most recently undone is last on the stack
+                    for (index, entry) in history_resource.redo_stack.iter().rev().enumerate() {
+                        let text = egui::RichText::new(&entry.label).weak();
+                        if ui.add(egui::SelectableLabel::new(false, text)).clicked() {
+                            action = Some(HistoryAction::JumpTo(applied + index + 1));
+                        }
+                    }
+                });
+        });
+
+    if action.is_some() {
+        history_resource.action = action;
+    }
+}
+
+/// value of a centerline sample shown in a profile plot
+type ProfileValue = fn(&CenterlinePoint) -> f32;
+
+/// elevation, curvature, bank angle and width against arc length, the selected element is
+/// highlighted and the cursor is shared with the marker in the 3d view
+pub fn profile_system(
+    global_resource: Res<GlobalResource>,
+    centerline_resource: Res<CenterlineResource>,
+    mut profile_resource: ResMut<ProfileResource>,
+    mut contexts: EguiContexts,
+) {
+    if global_resource.app_mode != AppMode::Editor {
+        return;
+    }
+
+    let ctx = contexts.ctx_mut();
+
+    let points = &centerline_resource.points;
+    let selected = usize::try_from(global_resource.highlighted_track_index).ok();
+
+    let profiles: [(&str, ProfileValue); 4] = [
+        ("Elevation (ft)", |point| point.position.z),
+        ("Curvature (1/ft)", |point| point.curvature),
+        ("Bank Angle (deg)", |point| point.bank_angle),
+        ("Width (ft)", |point| point.width),
+    ];
+
+    let mut hovered_s = None;
+
+    egui::Window::new("Profiles")
+        .default_open(false)
+        .default_width(500.)
+        .show(ctx, |ui| {
+            for (name, value) in profiles {
+                ui.label(name);
+
+                // every plot spans the whole track so the arc length axes line up
+                let plot = Plot::new(name)
+                    .height(PROFILE_PLOT_HEIGHT)
+                    .include_x(0.)
+                    .include_x(centerline_resource.length)
+                    .allow_drag(false)
+                    .allow_zoom(false)
+                    .allow_scroll(false)
+                    .allow_boxed_zoom(false);
+
+                plot.show(ui, |plot_ui| {
+                    let series: PlotPoints = points
+                        .iter()
+                        .map(|point| [point.s as f64, value(point) as f64])
+                        .collect();
+                    plot_ui.line(Line::new(series).color(egui::Color32::LIGHT_BLUE));
+
+                    if let Some(selected) = selected {
+                        let series: PlotPoints = points
+                            .iter()
+                            .filter(|point| point.element_index == selected)
+                            .map(|point| [point.s as f64, value(point) as f64])
+                            .collect();
+                        plot_ui.line(
+                            Line::new(series)
+                                .color(egui::Color32::from_rgb(255, 140, 0))
+                                .width(3.),
+                        );
+                    }
+
+                    if let Some(s) = profile_resource.cursor_s {
+                        plot_ui.vline(VLine::new(s).color(egui::Color32::YELLOW));
+                    }
+
+                    if plot_ui.plot_hovered() {
+                        if let Some(pointer) = plot_ui.pointer_coordinate() {
+                            hovered_s =
+                                Some((pointer.x as f32).clamp(0., centerline_resource.length));
+                        }
+                    }
+                });
+            }
+        });
+
+    // only write on change, the 3d view keeps its cursor while the plots aren't hovered
+    let plot_hovered = hovered_s.is_some();
+    if profile_resource.plot_hovered != plot_hovered {
+        profile_resource.plot_hovered = plot_hovered;
+    }
+    if plot_hovered && profile_resource.cursor_s != hovered_s {
+        profile_resource.cursor_s = hovered_s;
+    }
+}
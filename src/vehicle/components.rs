@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+pub const GRAVITY: f32 = 32.2; // ft/s^2
+pub const MAX_FALL_DEPTH: f32 = -50.; // vehicle is reset to the track start below this height
+
+// Raycast suspension vehicle. The body frame is +X forward, +Y left and +Z up, matching the
+// track element frame used by create_arc_transform.
+#[derive(Component)]
+pub struct Vehicle {
+    // body
+    pub mass: f32,
+    pub inertia: Vec3, // principal moments of inertia in body frame
+    pub body_size: Vec3,
+    pub drag: f32,
+    pub angular_damping: f32,
+
+    // suspension, mount points in body frame ordered front left, front right, rear left, rear right
+    pub wheel_mounts: [Vec3; 4],
+    pub wheel_radius: f32,
+    pub rest_length: f32,
+    pub spring_stiffness: f32,
+    pub damping: f32,
+
+    // tires and drivetrain
+    pub max_steer_angle: f32, // radians
+    pub engine_force: f32,
+    pub brake_force: f32,
+    pub friction: f32,
+    pub cornering_stiffness: f32,
+
+    // state
+    pub velocity: Vec3,
+    pub angular_velocity: Vec3,
+    pub steer_angle: f32,
+    pub suspension_lengths: [f32; 4],
+    pub wheel_spin: [f32; 4],
+    pub wheel_contact: [bool; 4],
+}
+
+impl Default for Vehicle {
+    fn default() -> Self {
+        let body_size = Vec3::new(3., 1.6, 0.6);
+        let mass = 1.;
+
+        Vehicle {
+            mass,
+            inertia: Vec3::new(
+                mass / 12. * (body_size.y * body_size.y + body_size.z * body_size.z),
+                mass / 12. * (body_size.x * body_size.x + body_size.z * body_size.z),
+                mass / 12. * (body_size.x * body_size.x + body_size.y * body_size.y),
+            ),
+            body_size,
+            drag: 0.006,
+            angular_damping: 0.5,
+
+            wheel_mounts: [
+                Vec3::new(1.1, 0.7, -0.1),
+                Vec3::new(1.1, -0.7, -0.1),
+                Vec3::new(-1.1, 0.7, -0.1),
+                Vec3::new(-1.1, -0.7, -0.1),
+            ],
+            wheel_radius: 0.35,
+            rest_length: 0.5,
+            spring_stiffness: 40.,
+            damping: 3.2,
+
+            max_steer_angle: 0.5,
+            engine_force: 24.,
+            brake_force: 40.,
+            friction: 1.2,
+            cornering_stiffness: 6.,
+
+            velocity: Vec3::ZERO,
+            angular_velocity: Vec3::ZERO,
+            steer_angle: 0.,
+            suspension_lengths: [0.5; 4],
+            wheel_spin: [0.; 4],
+            wheel_contact: [false; 4],
+        }
+    }
+}
+
+impl Vehicle {
+    /// forward speed along the body x axis
+    pub fn forward_speed(&self, transform: &Transform) -> f32 {
+        self.velocity.dot(transform.rotation * Vec3::X)
+    }
+
+    /// front wheels steer, rear wheels drive
+    pub fn is_front_wheel(index: usize) -> bool {
+        index < 2
+    }
+}
+
+/// driver commands, written by keyboard input or any other driver
+#[derive(Component, Default)]
+pub struct VehicleInput {
+    pub throttle: f32, // -1 (reverse) to 1
+    pub brake: f32,    // 0 to 1
+    pub steer: f32,    // -1 (right) to 1 (left)
+}
+
+// labels
+// vehicle driven with the keyboard and followed by the chase camera
+#[derive(Component)]
+pub struct PlayerControlled;
+
+#[derive(Component)]
+pub struct VehicleWheel {
+    pub index: usize,
+}
+
+// despawn when leaving play mode
+#[derive(Component)]
+pub struct VehicleElement;
+
+/// world space bounds of each track mesh, rebuilt whenever the track is respawned, used to skip
+/// meshes when ray casting for the ground
+#[derive(Resource, Default)]
+pub struct TrackColliderResource {
+    pub track_bounds_list: Vec<(Vec3, Vec3)>,
+}
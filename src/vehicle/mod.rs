@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+pub mod components;
+pub mod systems;
+
+use components::*;
+use systems::*;
+
+pub struct VehiclePlugin;
+
+impl Plugin for VehiclePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrackColliderResource>()
+            .add_system(update_track_collider)
+            .add_system(toggle_play_mode)
+            .add_system(player_vehicle_input)
+            .add_system(update_wheels)
+            .add_system(vehicle_physics.in_schedule(CoreSchedule::FixedUpdate));
+    }
+}
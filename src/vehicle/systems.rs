@@ -0,0 +1,392 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::*,
+    terrain::systems::terrain_noise_height,
+    tools::raycast::{interpolate_vec4_attribute, mesh_world_bounds, raycast_mesh},
+    vehicle::components::*,
+};
+
+pub const STEER_RATE: f32 = 3.; // steering input change per second
+pub const NORMAL_SAMPLE_DELTA: f32 = 0.1; // ground normal finite difference step
+pub const SPAWN_DISTANCE: f32 = 2.; // spawn distance along the first track element
+pub const SPAWN_HEIGHT: f32 = 1.; // spawn height above the track
+
+/// rebuild mesh bounds when the track has been respawned
+pub fn update_track_collider(
+    meshes: Res<Assets<Mesh>>,
+    mesh_resource: Res<MeshResource>,
+    mut collider_resource: ResMut<TrackColliderResource>,
+) {
+    if !mesh_resource.is_changed() {
+        return;
+    }
+
+    collider_resource.track_bounds_list = mesh_resource
+        .track_mesh_list
+        .iter()
+        .zip(mesh_resource.track_mesh_transform_list.iter())
+        .map(|(mesh_handle, transform)| {
+            meshes
+                .get(mesh_handle)
+                .and_then(|mesh| mesh_world_bounds(mesh, transform))
+                .unwrap_or((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)))
+        })
+        .collect();
+}
+
+/// spawns the player vehicle when entering play mode and removes it when leaving
+pub fn toggle_play_mode(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut prev_mode: Local<AppMode>,
+    global_resource: Res<GlobalResource>,
+    mesh_resource: Res<MeshResource>,
+    vehicle_query: Query<Entity, With<VehicleElement>>,
+) {
+    if *prev_mode == global_resource.app_mode {
+        return;
+    }
+    *prev_mode = global_resource.app_mode;
+
+    for entity in vehicle_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if global_resource.app_mode == AppMode::Play {
+        spawn_vehicle(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            track_start_transform(&mesh_resource),
+            Color::rgb(0.8, 0.1, 0.1),
+        );
+    }
+}
+
+/// start pose of the track lifted above the surface
+pub fn track_start_transform(mesh_resource: &MeshResource) -> Transform {
+    let start = mesh_resource
+        .track_mesh_transform_list
+        .first()
+        .copied()
+        .unwrap_or_default();
+
+    start * Transform::from_translation(Vec3::new(SPAWN_DISTANCE, 0., SPAWN_HEIGHT))
+}
+
+/// spawns a vehicle body with four wheels
+pub fn spawn_vehicle(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    transform: Transform,
+    color: Color,
+) -> Entity {
+    let vehicle = Vehicle::default();
+
+    let body_mesh = meshes.add(Mesh::from(shape::Box::new(
+        vehicle.body_size.x,
+        vehicle.body_size.y,
+        vehicle.body_size.z,
+    )));
+    let wheel_mesh = meshes.add(Mesh::from(shape::Cylinder {
+        radius: vehicle.wheel_radius,
+        height: 0.25,
+        ..default()
+    }));
+    let body_material = materials.add(color.into());
+    let wheel_material = materials.add(Color::rgb(0.1, 0.1, 0.1).into());
+
+    let wheel_mounts = vehicle.wheel_mounts;
+    let rest_length = vehicle.rest_length;
+
+    commands
+        .spawn((
+            PbrBundle {
+                mesh: body_mesh,
+                material: body_material,
+                transform,
+                ..default()
+            },
+            vehicle,
+            VehicleInput::default(),
+            PlayerControlled,
+            VehicleElement,
+        ))
+        .with_children(|parent| {
+            for (index, mount) in wheel_mounts.iter().enumerate() {
+                parent.spawn((
+                    PbrBundle {
+                        mesh: wheel_mesh.clone(),
+                        material: wheel_material.clone(),
+                        transform: Transform::from_translation(*mount - Vec3::Z * rest_length),
+                        ..default()
+                    },
+                    VehicleWheel { index },
+                ));
+            }
+        })
+        .id()
+}
+
+/// keyboard driving: W/S or arrows for throttle and brake, A/D or arrows to steer, R to reset
+pub fn player_vehicle_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mesh_resource: Res<MeshResource>,
+    mut vehicle_query: Query<
+        (&mut Vehicle, &mut VehicleInput, &mut Transform),
+        With<PlayerControlled>,
+    >,
+) {
+    for (mut vehicle, mut input, mut transform) in vehicle_query.iter_mut() {
+        let forward = keyboard_input.any_pressed([KeyCode::W, KeyCode::Up]);
+        let back = keyboard_input.any_pressed([KeyCode::S, KeyCode::Down]);
+        let left = keyboard_input.any_pressed([KeyCode::A, KeyCode::Left]);
+        let right = keyboard_input.any_pressed([KeyCode::D, KeyCode::Right]);
+
+        // back brakes while rolling forward and reverses once stopped
+        let speed = vehicle.forward_speed(&transform);
+        input.throttle = 0.;
+        input.brake = 0.;
+        if forward {
+            input.throttle = 1.;
+        }
+        if back {
+            if speed > 1. {
+                input.brake = 1.;
+            } else {
+                input.throttle = -0.5;
+            }
+        }
+
+        // ease steering toward the pressed direction
+        let target_steer = left as i32 as f32 - right as i32 as f32;
+        let max_delta = STEER_RATE * time.delta_seconds();
+        input.steer += (target_steer - input.steer).clamp(-max_delta, max_delta);
+
+        if keyboard_input.just_pressed(KeyCode::R) || transform.translation.z < MAX_FALL_DEPTH {
+            reset_vehicle(
+                &mut vehicle,
+                &mut transform,
+                track_start_transform(&mesh_resource),
+            );
+        }
+    }
+}
+
+/// places a vehicle at rest at transform
+pub fn reset_vehicle(vehicle: &mut Vehicle, transform: &mut Transform, new_transform: Transform) {
+    *transform = new_transform;
+    vehicle.velocity = Vec3::ZERO;
+    vehicle.angular_velocity = Vec3::ZERO;
+    vehicle.suspension_lengths = [vehicle.rest_length; 4];
+}
+
+/// height of the displaced terrain at p, the same blend of track height and noise height as the
+/// terrain shader: z = z_track * (1 - red) + z_noise * red
+pub fn ground_height(
+    p: Vec2,
+    meshes: &Assets<Mesh>,
+    global_resource: &GlobalResource,
+    mesh_resource: &MeshResource,
+    collider_resource: &TrackColliderResource,
+) -> f32 {
+    let noise_height = terrain_noise_height(global_resource, p);
+
+    // the height map camera looks down, so the highest track surface wins
+    let mut track: Option<(f32, f32)> = None; // (height, blend)
+
+    for ((mesh_handle, transform), (min, max)) in mesh_resource
+        .track_mesh_list
+        .iter()
+        .zip(mesh_resource.track_mesh_transform_list.iter())
+        .zip(collider_resource.track_bounds_list.iter())
+    {
+        if p.x < min.x || p.x > max.x || p.y < min.y || p.y > max.y {
+            continue;
+        }
+
+        let Some(mesh) = meshes.get(mesh_handle) else {
+            continue;
+        };
+
+        let origin = Vec3::new(p.x, p.y, max.z + 1.);
+        if let Some(hit) = raycast_mesh(mesh, transform, origin, Vec3::NEG_Z) {
+            if track.is_none_or(|(height, _)| hit.position.z > height) {
+                let blend = interpolate_vec4_attribute(mesh, Mesh::ATTRIBUTE_COLOR, &hit)
+                    .map_or(0., |color| color.x);
+                track = Some((hit.position.z, blend));
+            }
+        }
+    }
+
+    match track {
+        Some((track_height, blend)) => track_height * (1. - blend) + noise_height * blend,
+        None => noise_height,
+    }
+}
+
+/// raycast suspension and tire model, integrated on the fixed timestep
+pub fn vehicle_physics(
+    fixed_time: Res<FixedTime>,
+    meshes: Res<Assets<Mesh>>,
+    global_resource: Res<GlobalResource>,
+    mesh_resource: Res<MeshResource>,
+    collider_resource: Res<TrackColliderResource>,
+    mut vehicle_query: Query<(&mut Vehicle, &VehicleInput, &mut Transform)>,
+) {
+    let dt = fixed_time.period.as_secs_f32();
+
+    let height_at = |p: Vec2| {
+        ground_height(
+            p,
+            &meshes,
+            &global_resource,
+            &mesh_resource,
+            &collider_resource,
+        )
+    };
+
+    for (mut vehicle, input, mut transform) in vehicle_query.iter_mut() {
+        let vehicle = &mut *vehicle;
+        let rotation = transform.rotation;
+        let body_up = rotation * Vec3::Z;
+        let body_down = -body_up;
+
+        let mut force = Vec3::NEG_Z * GRAVITY * vehicle.mass;
+        let mut torque = Vec3::ZERO;
+
+        vehicle.steer_angle = input.steer * vehicle.max_steer_angle;
+
+        for index in 0..4 {
+            let mount = transform.translation + rotation * vehicle.wheel_mounts[index];
+            let max_length = vehicle.rest_length + vehicle.wheel_radius;
+
+            // step along the suspension ray to the ground, refined once at the first estimate
+            let mut contact = None;
+            if body_down.z < -0.1 {
+                let mut t = (mount.z - height_at(mount.truncate())) / -body_down.z;
+                let estimate = mount + body_down * t;
+                t = (mount.z - height_at(estimate.truncate())) / -body_down.z;
+
+                // mounts sunk below the ground still push back at full compression
+                if (-max_length..=max_length).contains(&t) {
+                    contact = Some((t.max(0.), mount + body_down * t));
+                }
+            }
+
+            let Some((hit_distance, hit_point)) = contact else {
+                vehicle.suspension_lengths[index] = vehicle.rest_length;
+                vehicle.wheel_contact[index] = false;
+                continue;
+            };
+            vehicle.wheel_contact[index] = true;
+
+            // ground normal from finite differences
+            let h = hit_point.z;
+            let h_x = height_at(hit_point.truncate() + Vec2::X * NORMAL_SAMPLE_DELTA);
+            let h_y = height_at(hit_point.truncate() + Vec2::Y * NORMAL_SAMPLE_DELTA);
+            let normal = Vec3::new(
+                (h - h_x) / NORMAL_SAMPLE_DELTA,
+                (h - h_y) / NORMAL_SAMPLE_DELTA,
+                1.,
+            )
+            .normalize();
+
+            // spring and damper along the body up axis
+            let length = (hit_distance - vehicle.wheel_radius).max(0.);
+            let compression = vehicle.rest_length - length;
+            let compression_velocity = (vehicle.suspension_lengths[index] - length) / dt;
+            vehicle.suspension_lengths[index] = length;
+
+            let load = (vehicle.spring_stiffness * compression
+                + vehicle.damping * compression_velocity)
+                .max(0.);
+
+            // tire frame projected on the ground
+            let steer = if Vehicle::is_front_wheel(index) {
+                vehicle.steer_angle
+            } else {
+                0.
+            };
+            let wheel_forward = rotation * Quat::from_rotation_z(steer) * Vec3::X;
+            let forward = (wheel_forward - normal * wheel_forward.dot(normal)).normalize_or_zero();
+            let side = normal.cross(forward);
+
+            let r = hit_point - transform.translation;
+            let point_velocity = vehicle.velocity + vehicle.angular_velocity.cross(r);
+            let long_velocity = point_velocity.dot(forward);
+            let lat_velocity = point_velocity.dot(side);
+
+            // longitudinal: rear wheel drive and four wheel brakes
+            let mut long_force = 0.;
+            if !Vehicle::is_front_wheel(index) {
+                long_force += input.throttle * vehicle.engine_force * vehicle.mass / 2.;
+            }
+            long_force -= input.brake * vehicle.brake_force * vehicle.mass / 4.
+                * long_velocity.clamp(-1., 1.);
+
+            // lateral: linear in slip angle
+            let slip_angle = lat_velocity.atan2(long_velocity.abs().max(5.));
+            let lat_force = -vehicle.cornering_stiffness * slip_angle * load;
+
+            // friction circle
+            let mut tire_force = Vec2::new(long_force, lat_force);
+            let max_force = vehicle.friction * load;
+            if tire_force.length() > max_force {
+                tire_force = tire_force.normalize_or_zero() * max_force;
+            }
+
+            let wheel_force = body_up * load + forward * tire_force.x + side * tire_force.y;
+            force += wheel_force;
+            torque += r.cross(wheel_force);
+
+            vehicle.wheel_spin[index] += long_velocity / vehicle.wheel_radius * dt;
+        }
+
+        // aerodynamic drag
+        let speed = vehicle.velocity.length();
+        force -= vehicle.velocity * speed * vehicle.drag * vehicle.mass;
+
+        // integrate, angular acceleration in body frame with principal inertia
+        let acceleration = force / vehicle.mass;
+        vehicle.velocity += acceleration * dt;
+
+        let body_torque = rotation.inverse() * torque;
+        let body_angular_acceleration = body_torque / vehicle.inertia;
+        vehicle.angular_velocity += rotation * body_angular_acceleration * dt;
+        vehicle.angular_velocity *= 1. - vehicle.angular_damping * dt;
+
+        let velocity = vehicle.velocity;
+        let angular_velocity = vehicle.angular_velocity;
+        transform.translation += velocity * dt;
+        transform.rotation =
+            (Quat::from_scaled_axis(angular_velocity * dt) * transform.rotation).normalize();
+    }
+}
+
+/// moves wheel meshes to the suspension length, steering and spin of their vehicle
+pub fn update_wheels(
+    vehicle_query: Query<&Vehicle>,
+    mut wheel_query: Query<(&VehicleWheel, &Parent, &mut Transform)>,
+) {
+    for (wheel, parent, mut transform) in wheel_query.iter_mut() {
+        let Ok(vehicle) = vehicle_query.get(parent.get()) else {
+            continue;
+        };
+
+        let steer = if Vehicle::is_front_wheel(wheel.index) {
+            vehicle.steer_angle
+        } else {
+            0.
+        };
+
+        transform.translation =
+            vehicle.wheel_mounts[wheel.index] - Vec3::Z * vehicle.suspension_lengths[wheel.index];
+        transform.rotation =
+            Quat::from_rotation_z(steer) * Quat::from_rotation_y(vehicle.wheel_spin[wheel.index]);
+    }
+}
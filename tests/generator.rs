@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+
+use race_track::{
+    components::TrackElement2D,
+    track::{
+        components::CLOSED_TRACK_TOLERANCE,
+        generator::*,
+        systems::{element_frame, element_length, element_start_transform},
+    },
+};
+
+const SEEDS: [u64; 6] = [0, 1, 2, 7, 42, 1234];
+
+/// centerline points at most step apart, from the identity start transform
+fn sample_centerline(track_list: &[TrackElement2D], step: f32) -> Vec<Vec2> {
+    let mut points = vec![];
+
+    for (index, track) in track_list.iter().enumerate() {
+        let start = element_start_transform(&Transform::IDENTITY, track_list, index);
+        let count = (element_length(track) / step).ceil().max(1.) as usize;
+
+        for sample in 0..count {
+            let ratio = sample as f32 / count as f32;
+            points.push((start * element_frame(track, ratio)).translation.truncate());
+        }
+    }
+
+    points
+}
+
+fn segments_intersect(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+    let cross = |o: Vec2, p: Vec2, q: Vec2| (p - o).perp_dot(q - o);
+
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+
+    d1 * d2 < 0. && d3 * d4 < 0.
+}
+
+#[test]
+fn generated_circuits_are_closed() {
+    for seed in SEEDS {
+        let params = CircuitParams { seed, ..default() };
+        let track_list = generate_circuit(&params).unwrap();
+
+        let end = element_start_transform(&Transform::IDENTITY, &track_list, track_list.len());
+        assert!(
+            end.translation.length() < CLOSED_TRACK_TOLERANCE,
+            "seed {seed} ends {} ft from its start",
+            end.translation.length()
+        );
+    }
+}
+
+#[test]
+fn generated_circuits_do_not_cross_themselves() {
+    for seed in SEEDS {
+        let params = CircuitParams { seed, ..default() };
+        let track_list = generate_circuit(&params).unwrap();
+        let points = sample_centerline(&track_list, params.width / 2.);
+        let count = points.len();
+
+        for i in 0..count {
+            // neighbouring segments share a point
+            for j in i + 2..count {
+                if (j + 1) % count == i {
+                    continue;
+                }
+                assert!(
+                    !segments_intersect(
+                        points[i],
+                        points[(i + 1) % count],
+                        points[j],
+                        points[(j + 1) % count]
+                    ),
+                    "seed {seed} crosses itself at segments {i} and {j}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn generated_circuits_fit_the_area() {
+    for seed in SEEDS {
+        let params = CircuitParams {
+            seed,
+            area: Vec2::new(300., 150.),
+            ..default()
+        };
+        let track_list = generate_circuit(&params).unwrap();
+        let points = sample_centerline(&track_list, params.width / 2.);
+
+        let min = points
+            .iter()
+            .fold(Vec2::splat(f32::MAX), |min, p| min.min(*p));
+        let max = points
+            .iter()
+            .fold(Vec2::splat(f32::MIN), |max, p| max.max(*p));
+        let size = max - min + params.width;
+
+        assert!(
+            size.x <= params.area.x && size.y <= params.area.y,
+            "seed {seed} is {size} ft, the area is {}",
+            params.area
+        );
+    }
+}
+
+#[test]
+fn same_seed_gives_the_same_circuit() {
+    let params = CircuitParams {
+        seed: 42,
+        ..default()
+    };
+
+    assert_eq!(generate_circuit(&params), generate_circuit(&params));
+}
+
+#[test]
+fn invalid_params_are_rejected() {
+    let defaults = CircuitParams::default();
+    let invalid = [
+        CircuitParams {
+            corners: 2,
+            ..defaults
+        },
+        CircuitParams {
+            width: 0.,
+            ..defaults
+        },
+        CircuitParams {
+            target_length: -1.,
+            ..defaults
+        },
+        CircuitParams {
+            min_radius: defaults.width / 2.,
+            ..defaults
+        },
+        CircuitParams {
+            area: Vec2::new(defaults.min_radius, 250.),
+            ..defaults
+        },
+        CircuitParams {
+            max_bank_angle: 61.,
+            ..defaults
+        },
+        CircuitParams {
+            max_bank_angle: -1.,
+            ..defaults
+        },
+    ];
+
+    for params in invalid {
+        assert!(
+            matches!(
+                generate_circuit(&params),
+                Err(GeneratorError::InvalidParams(_))
+            ),
+            "{params:?} was accepted"
+        );
+    }
+}